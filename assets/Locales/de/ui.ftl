@@ -0,0 +1,54 @@
+# the name of this language, written in it
+language-name = Deutsch
+
+## title screen
+menu-title = Deeper and Deeper
+menu-new-game = Neues Spiel
+menu-continue = Fortsetzen
+menu-load = Laden
+menu-settings = Einstellungen
+menu-credits = Mitwirkende
+menu-quit = Beenden
+menu-back = Zurück
+
+## pause menu
+pause-title = Pausiert
+pause-resume = Weiter
+pause-return = Zurück zum Schiff
+
+## settings
+settings-title = Einstellungen
+settings-music = Musik: { $percent }%
+settings-sounds = Geräusche: { $percent }%
+settings-window = Fenster: { $mode }
+window-windowed = Fenster
+window-borderless = Randlos
+window-fullscreen = Vollbild
+settings-resolution = Auflösung: { $width }x{ $height }
+settings-text-speed = Textgeschwindigkeit: { $speed }
+text-speed-slow = Langsam
+text-speed-normal = Normal
+text-speed-fast = Schnell
+text-speed-instant = Sofort
+text-speed-custom = Eigene
+settings-language = Sprache: { $language }
+settings-diagnostics = Diagnose: { $state }
+settings-on = An
+settings-off = Aus
+controls-title = Steuerung
+controls-waiting = { $action }: Taste oder Knopf drücken
+controls-reset = Zurücksetzen
+
+## exploring
+hud-oxygen = Sauerstoff: { $current }/{ $capacity } (-{ $rate }/s)
+hud-health = Gesundheit: { $current }/{ $max }
+hud-poles = Stangen: { $poles }
+hud-return = Du musst jetzt zurück!
+
+## on the ship
+naming-question = Wie heißt du, Captain?
+naming-confirm = Enter zum Bestätigen
+backlog-title = Verlauf
+choice-new = NEU
+dialogue-skip = SKIP
+dialogue-auto = AUTO
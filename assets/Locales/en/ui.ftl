@@ -0,0 +1,54 @@
+# the name of this language, written in it
+language-name = English
+
+## title screen
+menu-title = Deeper and Deeper
+menu-new-game = New Game
+menu-continue = Continue
+menu-load = Load
+menu-settings = Settings
+menu-credits = Credits
+menu-quit = Quit
+menu-back = Back
+
+## pause menu
+pause-title = Paused
+pause-resume = Resume
+pause-return = Return to ship
+
+## settings
+settings-title = Settings
+settings-music = Music: { $percent }%
+settings-sounds = Sounds: { $percent }%
+settings-window = Window: { $mode }
+window-windowed = Windowed
+window-borderless = Borderless
+window-fullscreen = Fullscreen
+settings-resolution = Resolution: { $width }x{ $height }
+settings-text-speed = Text speed: { $speed }
+text-speed-slow = Slow
+text-speed-normal = Normal
+text-speed-fast = Fast
+text-speed-instant = Instant
+text-speed-custom = Custom
+settings-language = Language: { $language }
+settings-diagnostics = Diagnostics: { $state }
+settings-on = On
+settings-off = Off
+controls-title = Controls
+controls-waiting = { $action }: press a key or button
+controls-reset = Reset to defaults
+
+## exploring
+hud-oxygen = Oxygen: { $current }/{ $capacity } (-{ $rate }/s)
+hud-health = Health: { $current }/{ $max }
+hud-poles = Poles: { $poles }
+hud-return = You must return now!
+
+## on the ship
+naming-question = What's your name, Captain?
+naming-confirm = Enter to confirm
+backlog-title = Backlog
+choice-new = NEW
+dialogue-skip = SKIP
+dialogue-auto = AUTO
@@ -3,18 +3,48 @@
 //    picking: Vec<option>,
 //}
 
+pub mod backlog;
+pub mod markup;
+pub mod naming;
+pub mod read_lines;
+pub mod roster;
+
 use super::{despawn_screen, GameState};
-use crate::load;
+use crate::{
+    input::Action,
+    load,
+    locale::{Locale, SceneTranslation},
+    save::SaveData,
+    settings::{Music, Settings},
+};
+use backlog::{BacklogEntry, BacklogState};
 use bevy::{
+    audio::{Pitch, Volume},
+    ecs::system::SystemParam,
+    input::mouse::MouseWheel,
     prelude::*,
     text::{LineBreak, TextBounds},
     window::PrimaryWindow,
 };
-use serde::Deserialize;
-use std::collections::HashMap;
-
-#[derive(Deserialize, Copy, Clone, Debug)]
-enum MissionType {
+use markup::{Markup, Rendered, Style};
+use read_lines::ReadLines;
+use roster::{crew_layout, Character, Speaker};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+// seconds a finished line stays up before auto-advance moves on
+const AUTO_ADVANCE_DELAY: f32 = 1.5;
+// seconds each already-read line is shown for while skipping
+const SKIP_DELAY: f32 = 0.08;
+// how much bigger [em] text is
+const EMPHASIS_SCALE: f32 = 1.2;
+// how far [shake] moves the line, in pixels
+const SHAKE: f32 = 2.5;
+// the ship screen's cursor on the exit icon, otherwise it's the crew member it's on
+const EXIT_CURSOR: isize = -1;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub enum MissionType {
     Water,
     Explore,
     Oil,
@@ -22,32 +52,21 @@ enum MissionType {
     Tutorial,
 }
 
-#[derive(Deserialize, Debug, Copy, Clone)]
-enum CharactersType {
-    Joe,
-    Jule,
-    Carle,
-    Fredrick,
-    Diedrick,
-    Cat,
-    Liv,
-    You,
-}
-
-struct CharactersStatus {
-    character: CharactersType,
-    current_dialogue: String,
-}
-
 #[derive(Resource)]
 pub struct DatingContext {
-    all_characters: Vec<CharactersStatus>,
+    characters: Vec<Character>,
     day: usize,
     cursor: isize,
     selected_scene: DatingScene,
     pub flags: HashMap<String, isize>,
     gathered_mission: Vec<MissionType>,
     scenes: Vec<DatingScene>,
+    // false until the opening scene has started
+    initialized: bool,
+    /// What the player is called, `{Captain}` in dialogue.
+    captain: String,
+    /// Everything said and picked, oldest first.
+    backlog: Vec<BacklogEntry>,
 }
 
 impl DatingContext {
@@ -63,12 +82,43 @@ impl DatingContext {
     pub fn get_flag(&self, flag: &str) -> isize {
         *self.flags.get(flag).unwrap_or(&0)
     }
+
+    /// What `{name}` stands for in dialogue: the captain's name, or else the flag's value.
+    fn variable(&self, name: &str) -> String {
+        if name == "Captain" {
+            self.captain.clone()
+        } else {
+            self.get_flag(name).to_string()
+        }
+    }
+
+    pub fn to_save(&self, seed: u64) -> SaveData {
+        SaveData {
+            seed,
+            flags: self.flags.clone(),
+            missions: self.gathered_mission.clone(),
+            explored: HashMap::new(),
+            backlog: self.backlog.clone(),
+            captain: Some(self.captain.clone()),
+        }
+    }
+
+    pub fn restore(&mut self, data: &SaveData) {
+        self.flags = data.flags.clone();
+        self.gathered_mission = data.missions.clone();
+        self.backlog = data.backlog.clone();
+        if let Some(captain) = &data.captain {
+            self.captain = captain.clone();
+        }
+        self.initialized = true;
+    }
 }
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum DatingState {
     #[default]
     Noting,
+    Naming,
     Chilling,
     Talking,
     Choosing,
@@ -77,23 +127,123 @@ pub enum DatingState {
 #[derive(Deserialize, Debug, Clone)]
 pub struct DatingScene {
     id: SceneID,
-    text: Vec<(Option<CharactersType>, String)>,
+    text: Vec<(Option<Speaker>, String)>,
     outcome: Option<Vec<(Flag, isize)>>,
-    choice: Option<Vec<(String, String)>>,
+    choice: Option<Vec<Choice>>,
     mission: Option<MissionType>,
-    #[serde(default, rename = "scene")]
+    // some of the scene files were written with "scenes"
+    #[serde(default, rename = "scene", alias = "scenes")]
     next_scene: Vec<(Cond, SceneID)>,
     #[serde(default)]
     black: bool,
 }
 
+/// An option in a scene's `choice` list.
+///
+/// Either `["label", "NextScene"]`, or an object for options that depend on flags:
+/// `{"label": ..., "scene": ..., "condition": [["Flag", 1]], "locked": "why", "outcome": [["Flag", 1]]}`.
+/// An option whose condition fails is hidden, or greyed out with the `locked` reason if it has one.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum Choice {
+    Plain(String, SceneID),
+    Conditional {
+        label: String,
+        scene: SceneID,
+        #[serde(default)]
+        condition: Cond,
+        #[serde(default)]
+        locked: Option<String>,
+        #[serde(default)]
+        outcome: Vec<(Flag, isize)>,
+    },
+}
+
+impl DatingScene {
+    /// Line `index` and who says it, in the language of `locale`.
+    fn line(&self, index: usize, locale: &Locale) -> Option<(Option<Speaker>, String)> {
+        let (speaker, line) = self.text.get(index)?;
+        Some((
+            speaker.clone(),
+            locale.line(&self.id, index, line).to_string(),
+        ))
+    }
+}
+
+/// The lines and choices of `scenes` that `translation` leaves out or breaks the markup of,
+/// and what it translates that isn't in `scenes` anymore, saying where they are.
+pub fn untranslated_text(
+    scenes: &[DatingScene],
+    translation: &HashMap<String, SceneTranslation>,
+) -> Vec<String> {
+    let empty = SceneTranslation::default();
+    let mut missing = vec![];
+    for scene in scenes {
+        let translated = translation.get(&scene.id).unwrap_or(&empty);
+        for index in 0..scene.text.len() {
+            if !translated.lines.contains_key(&index) {
+                missing.push(format!("{} line {index}", scene.id));
+            }
+        }
+        for (index, choice) in scene.choice.iter().flatten().enumerate() {
+            if !translated.choices.contains_key(&index) {
+                missing.push(format!("{} choice {index}", scene.id));
+            }
+            let locked = matches!(
+                choice,
+                Choice::Conditional {
+                    locked: Some(_),
+                    ..
+                }
+            );
+            if locked && !translated.locked.contains_key(&index) {
+                missing.push(format!("{} choice {index} locked reason", scene.id));
+            }
+        }
+    }
+
+    // left behind when the English scenes were changed
+    let mut ids = translation.keys().collect::<Vec<_>>();
+    ids.sort();
+    for id in ids {
+        let translated = &translation[id];
+        let Some(scene) = scenes.iter().find(|scene| &scene.id == id) else {
+            missing.push(format!("{id}: no such scene"));
+            continue;
+        };
+        let choices = scene.choice.as_deref().unwrap_or_default();
+        for index in translated.lines.keys().filter(|&&i| i >= scene.text.len()) {
+            missing.push(format!("{id} line {index}: no such line"));
+        }
+        for index in translated.choices.keys().filter(|&&i| i >= choices.len()) {
+            missing.push(format!("{id} choice {index}: no such choice"));
+        }
+        for &index in translated.locked.keys() {
+            let locked = matches!(
+                choices.get(index),
+                Some(Choice::Conditional {
+                    locked: Some(_),
+                    ..
+                })
+            );
+            if !locked {
+                missing.push(format!("{id} choice {index}: no such locked reason"));
+            }
+        }
+    }
+
+    missing.extend(markup::translation_markup_errors(translation));
+    missing
+}
+
 type Check = (Option<Flag>, isize);
 type SceneID = String;
 type Flag = String;
 type Cond = Vec<Check>;
 
+/// A sprite the mouse can point at, moving the cursor to the value it holds.
 #[derive(Component)]
-struct FollowsMouse;
+struct Clickable(isize);
 
 #[derive(Component)]
 struct Cursor(isize);
@@ -113,6 +263,98 @@ struct TalkObj;
 #[derive(Component)]
 struct TextBox(usize);
 
+/// Reveals the [`TextBox`] a character at a time, at the speed in [`Settings`].
+#[derive(Component, Default)]
+struct Typewriter {
+    line: Rendered,
+    speaker: Option<Speaker>,
+    /// The pitch of the speaker's blips, none for narration.
+    voice: Option<f32>,
+    /// Characters shown so far, counting fractions between frames.
+    shown: f32,
+    /// The next of the line's pauses to stop at.
+    next_pause: usize,
+    /// Seconds left of the pause the typing is stopped at.
+    paused_for: f32,
+    /// Seconds since the whole line was shown.
+    finished_for: f32,
+    /// Whether the line went into the backlog yet.
+    logged: bool,
+    /// Set for a new line, until its spans are spawned.
+    fresh: bool,
+}
+
+impl Typewriter {
+    fn new(line: &str, speaker: Option<Speaker>, context: &DatingContext) -> Self {
+        // load_scenes refuses broken markup, a broken translation is logged and shown as is
+        let markup = Markup::parse(line).unwrap_or_else(|_| Markup::plain(line));
+        let voice = speaker
+            .as_ref()
+            .and_then(|speaker| context.character(&speaker.character))
+            .map(|character| character.voice);
+        Typewriter {
+            line: markup.render(|name| context.variable(name)),
+            speaker,
+            voice,
+            fresh: true,
+            ..default()
+        }
+    }
+
+    fn start(&mut self, line: &str, speaker: Option<Speaker>, context: &DatingContext) {
+        *self = Typewriter::new(line, speaker, context);
+    }
+
+    fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.line.spans.iter().flat_map(|(text, _)| text.chars())
+    }
+
+    /// The line without its markup.
+    fn text(&self) -> String {
+        self.chars().collect()
+    }
+
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+
+    fn done(&self) -> bool {
+        self.shown as usize >= self.length()
+            && self.next_pause >= self.line.pauses.len()
+            && self.paused_for <= 0.0
+    }
+
+    fn finish(&mut self) {
+        self.shown = self.length() as f32;
+        self.next_pause = self.line.pauses.len();
+        self.paused_for = 0.0;
+    }
+
+    /// Whether any shaking text is on screen yet.
+    fn shaking(&self) -> bool {
+        let shown = self.shown as usize;
+        let mut start = 0;
+        self.line.spans.iter().any(|(text, style)| {
+            let begins = start;
+            start += text.chars().count();
+            style.shake && begins < shown
+        })
+    }
+}
+
+/// Hands-free ways through dialogue, toggled while talking.
+#[derive(Resource, Default)]
+struct DialogueMode {
+    /// Moves on by itself a little after each line is shown.
+    auto: bool,
+    /// Races through lines that were read before, stopping at the first new one.
+    skip: bool,
+}
+
+/// Shows which [`DialogueMode`] is on.
+#[derive(Component)]
+struct ModeIndicator;
+
 #[derive(Component)]
 struct NameBox;
 
@@ -126,58 +368,29 @@ struct DatingOption;
 struct ChoiceObj(String);
 
 pub fn dating_sim_plugin(app: &mut App) {
-    let all_scenes = load::load_scenes();
-
-    let janitor_joe = CharactersStatus {
-        character: CharactersType::Joe,
-        current_dialogue: "JoeInit".to_string(),
-    };
-
-    let cat = CharactersStatus {
-        character: CharactersType::Cat,
-        current_dialogue: "CatInit".to_string(),
-    };
-
-    let granny = CharactersStatus {
-        character: CharactersType::Jule,
-        current_dialogue: "JuleInit".to_string(),
-    };
-
-    let twin1 = CharactersStatus {
-        character: CharactersType::Fredrick,
-        current_dialogue: "FredrickInit".to_string(),
-    };
-
-    let twin2 = CharactersStatus {
-        character: CharactersType::Diedrick,
-        current_dialogue: "DiedrickInit".to_string(),
-    };
-
-    let carly = CharactersStatus {
-        character: CharactersType::Carle,
-        current_dialogue: "CarleInit".to_string(),
-    };
-
-    let liv = CharactersStatus {
-        character: CharactersType::Liv,
-        current_dialogue: "LivInit".to_string(),
-    };
-
-    let characters = vec![janitor_joe, granny, cat, twin1, twin2, carly, liv];
+    let characters = load::load_characters();
+    let all_scenes = load::load_scenes(&characters);
 
     let first_scene = all_scenes[0].clone();
 
+    for missing in roster::missing_portraits(&all_scenes, &characters) {
+        log::warn!("no portrait for {missing}, using the neutral one");
+    }
+
     let mut initial_events: HashMap<String, isize> = HashMap::new();
     initial_events.insert("day".to_string(), 1);
 
     app.insert_resource(DatingContext {
-        all_characters: characters,
+        characters,
         day: 1,
         cursor: 0,
         selected_scene: first_scene,
         flags: initial_events.clone(),
         gathered_mission: vec![],
         scenes: all_scenes,
+        initialized: false,
+        captain: naming::DEFAULT_CAPTAIN.to_string(),
+        backlog: vec![],
     });
 
     app.init_state::<DatingState>();
@@ -200,7 +413,8 @@ pub fn dating_sim_plugin(app: &mut App) {
             cursor_action.run_if(in_state(DatingState::Chilling)),
         );
 
-    app.add_systems(OnEnter(DatingState::Choosing), on_choosing)
+    app.init_resource::<ChoiceScroll>()
+        .add_systems(OnEnter(DatingState::Choosing), on_choosing)
         .add_systems(Update, choose_move.run_if(in_state(DatingState::Choosing)))
         .add_systems(
             OnExit(DatingState::Choosing),
@@ -208,13 +422,22 @@ pub fn dating_sim_plugin(app: &mut App) {
         );
 
     //Choices
-    app.add_systems(OnEnter(DatingState::Talking), start_talking)
+    app.init_resource::<DialogueMode>()
+        .add_systems(OnEnter(DatingState::Talking), start_talking)
         .add_systems(
             Update,
-            talking_action.run_if(in_state(DatingState::Talking)),
+            (talking_action, type_text, shake_text, show_dialogue_mode)
+                .chain()
+                .run_if(in_state(DatingState::Talking).and(in_state(BacklogState::Closed))),
         )
         .add_systems(OnExit(DatingState::Talking), despawn_screen::<TalkObj>);
 
+    app.add_plugins((
+        backlog::backlog_plugin,
+        naming::naming_plugin,
+        read_lines::read_lines_plugin,
+    ));
+
     app.add_systems(
         OnExit(DatingState::Chilling),
         (despawn_screen::<Portrait>, despawn_screen::<MissionNot>),
@@ -224,18 +447,16 @@ pub fn dating_sim_plugin(app: &mut App) {
 fn on_dating_sim(
     mut commands: Commands,
     mut tmp: ResMut<NextState<DatingState>>,
-    mut did_init: Local<bool>,
-    mut context: ResMut<DatingContext>,
+    context: Res<DatingContext>,
     asset_server: Res<AssetServer>,
 ) {
     commands.spawn((
         AudioPlayer::new(asset_server.load("Music/Music_InShip.ogg")),
+        Music,
         DatingObj,
     ));
-    if !*did_init {
-        context.set_scene("Day1Morning");
-        *did_init = true;
-        tmp.set(DatingState::Talking);
+    if !context.initialized {
+        tmp.set(DatingState::Naming);
     } else {
         tmp.set(DatingState::Chilling);
     }
@@ -317,8 +538,15 @@ fn on_chill(
         ..default()
     };
 
+    let crew = context
+        .crew()
+        .map(|character| character.id.clone())
+        .collect::<Vec<_>>();
+    let (positions, portrait_size) = crew_layout(crew.len(), width);
+    context.cursor = context.cursor.clamp(EXIT_CURSOR, crew.len() as isize - 1);
+
     //Cursor initialisation
-    let cursor_size = Vec2::new(width / 8.0, width / 8.0);
+    let cursor_size = Vec2::splat(portrait_size * 1.125);
     let cursor_position = Vec2::new(0.0, 250.0);
     let enc = commands.spawn((
         Sprite::from_color(Color::srgb(0.25, 0.75, 0.25), cursor_size),
@@ -337,24 +565,19 @@ fn on_chill(
             ..Default::default()
         },
         Transform::from_translation(Vec2::new(0.0, -height / 3.0).extend(0.0)),
+        Clickable(EXIT_CURSOR),
         Portrait,
         DatingObj,
     ));
 
-    for (idx, i) in context.all_characters.iter().enumerate() {
-        let portrait = get_portrait(
-            i.character,
-            Vec2::new(size, size),
-            &asset_server,
-            context.flags.clone(),
-        );
-        let box_position = Vec2::new((idx as f32 * size * 1.2) - width / 2.5, 250.0);
-
-        let box_size = Vec2::new(size, size);
+    for (idx, (id, x)) in crew.into_iter().zip(positions).enumerate() {
+        let box_size = Vec2::new(portrait_size, portrait_size);
+        let portrait = context.portrait(&Speaker::from(id), box_size, &asset_server);
         commands
             .spawn((
                 Sprite::from_color(Color::srgb(0.75, 0.75, 0.75), box_size),
-                Transform::from_translation(box_position.extend(0.0)),
+                Transform::from_translation(Vec3::new(x, 250.0, 0.0)),
+                Clickable(idx as isize),
                 Portrait,
                 DatingObj,
             ))
@@ -383,71 +606,13 @@ fn on_chill(
     }
 }
 
-fn get_portrait(
-    character: CharactersType,
-    size: Vec2,
-    asset_server: &Res<AssetServer>,
-    flags: HashMap<String, isize>,
-) -> Sprite {
-    return match character {
-        CharactersType::Joe if !flags.get("JoeDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Janitor Joe-Recovered.png"),
-            ..Default::default()
-        },
-        CharactersType::Jule if !flags.get("JuleDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_General_Jule.png"),
-            ..Default::default()
-        },
-        CharactersType::Fredrick if !flags.get("FredrickDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Twin_Fredrick.png"),
-            ..Default::default()
-        },
-
-        CharactersType::Diedrick if !flags.get("DiedrickDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Twin_Dedrick.png"),
-            ..Default::default()
-        },
-
-        CharactersType::Carle if !flags.get("CarleDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Carly.png"),
-
-            ..Default::default()
-        },
-        CharactersType::Liv if !flags.get("LivDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Liv.png"),
-            ..Default::default()
-        },
-        CharactersType::Cat if !flags.get("CatDead").is_some_and(|x| *x == 1) => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_cat.png"),
-            ..Default::default()
-        },
-        CharactersType::You => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Player.png"),
-            ..Default::default()
-        },
-        _ => Sprite {
-            custom_size: Some(size),
-            image: asset_server.load("Portraits/Character_Liv.png"),
-            color: Color::srgba(0.00, 0.00, 0.00, 0.8),
-            ..default()
-        },
-    };
-}
-
 #[derive(Component)]
 struct EmptyScene;
 
 fn start_talking(
     mut commands: Commands,
     context: ResMut<DatingContext>,
+    locale: Res<Locale>,
     query: Query<&mut Transform, With<Cursor>>,
     asset_server: Res<AssetServer>,
     windows: Query<&mut Window, With<PrimaryWindow>>,
@@ -482,7 +647,10 @@ fn start_talking(
         background.color = Color::srgba(1.0, 1.0, 1.0, 1.0);
     }
 
-    let first = text.first().cloned().unwrap_or((None, String::new()));
+    let first = context
+        .selected_scene
+        .line(0, &locale)
+        .unwrap_or((None, String::new()));
 
     let dialogue = first.1.clone();
     let person = first.0;
@@ -500,7 +668,8 @@ fn start_talking(
             builder
                 .spawn((
                     TextColor(Color::srgb(0.0, 0.0, 0.0)),
-                    Text2d::new(dialogue),
+                    Text2d::default(),
+                    Typewriter::new(&dialogue, person.clone(), &context),
                     TextBox(0),
                     slightly_smaller_text_font.clone(),
                     TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
@@ -510,6 +679,17 @@ fn start_talking(
                     Transform::from_translation(Vec3::Z),
                 ))
                 .insert_if(EmptyScene, || text.is_empty());
+            builder.spawn((
+                Text2d::default(),
+                TextColor(Color::srgb(0.3, 0.3, 0.3)),
+                slightly_smaller_text_font.clone(),
+                Transform::from_translation(Vec3::new(
+                    talk_size.x * 0.38,
+                    -talk_size.y * 0.38,
+                    1.0,
+                )),
+                ModeIndicator,
+            ));
         });
 
     //Who is talking
@@ -529,7 +709,7 @@ fn start_talking(
             ))
             .with_children(|builder| {
                 builder.spawn((
-                    Text2d::new(format!("{:?}", real_person)),
+                    Text2d::new(context.display_name(&real_person.character, locale.language())),
                     TextColor(Color::srgb(0.0, 0.0, 0.0)),
                     NameBox,
                     slightly_smaller_text_font.clone(),
@@ -543,11 +723,10 @@ fn start_talking(
 
         //Look at sexy person talking
         commands.spawn((
-            get_portrait(
-                real_person,
+            context.portrait(
+                &real_person,
                 Vec2::new(width / 2.0, width / 2.0),
                 &asset_server,
-                context.flags.clone(),
             ),
             Transform::from_translation(Vec2::new(-width / 4.0, -height / 10.0).extend(-0.5)),
             TalkObj,
@@ -556,138 +735,346 @@ fn start_talking(
     }
 }
 
+/// A choice as it is offered to the player, once its conditions are checked.
+#[derive(Debug, Clone, PartialEq)]
+struct OfferedChoice {
+    label: String,
+    target: SceneID,
+    /// Shown greyed out and can't be picked.
+    locked: bool,
+    outcome: Vec<(Flag, isize)>,
+}
+
+/// The choices of `scene` the player gets to see, in order.
+fn offered_choices(
+    scene: &DatingScene,
+    flags: &HashMap<String, isize>,
+    locale: &Locale,
+) -> Vec<OfferedChoice> {
+    let id = &scene.id;
+    scene
+        .choice
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, choice)| match choice {
+            Choice::Plain(label, target) => Some(OfferedChoice {
+                label: locale.choice(id, index, label).to_string(),
+                target: target.clone(),
+                locked: false,
+                outcome: vec![],
+            }),
+            Choice::Conditional {
+                label,
+                scene,
+                condition,
+                locked,
+                outcome,
+            } => {
+                let met = cond_met(flags, condition);
+                let label = locale.choice(id, index, label);
+                let label = match locked {
+                    Some(reason) if !met => {
+                        format!("{label} ({})", locale.locked(id, index, reason))
+                    }
+                    _ => label.to_string(),
+                };
+                (met || locked.is_some()).then(|| OfferedChoice {
+                    label,
+                    target: scene.clone(),
+                    locked: !met,
+                    outcome: outcome.clone(),
+                })
+            }
+        })
+        .collect()
+}
+
+/// The first choice of the list on screen, when there are too many to show at once.
+#[derive(Resource, Default)]
+struct ChoiceScroll(usize);
+
+// more choices than this scroll
+const VISIBLE_CHOICES: usize = 4;
+const LOCKED_CHOICE_COLOR: Color = Color::srgb(0.35, 0.35, 0.40);
+
 fn on_choosing(
     mut commands: Commands,
     mut context: ResMut<DatingContext>,
-    query: Query<&mut Transform, With<Cursor>>,
     asset_server: Res<AssetServer>,
     windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut tmp: ResMut<NextState<DatingState>>,
+    mut scroll: ResMut<ChoiceScroll>,
+    read_lines: Res<ReadLines>,
+    locale: Res<Locale>,
 ) {
     let window = windows.single();
     let width = window.resolution.width();
     let height = window.resolution.height();
 
     let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
-    let text_font = TextFont {
-        font: font.clone(),
-        font_size: 50.0,
-        ..default()
-    };
-
     let slightly_smaller_text_font = TextFont {
         font,
         font_size: 27.0,
         ..default()
     };
 
-    // TODO fix option positioning
-    let option_size = Vec2::new(width / 2.0, height / 5.0);
-    let option_position_1 = Vec2::new(0.0, height / 4.0);
-    let option_position_2 = Vec2::new(0.0, -height / 4.0);
-    for (label, id) in context
-        .selected_scene
-        .choice
-        .as_ref()
-        .unwrap_or(&vec![])
-        .iter()
-    {
-        commands.spawn((
-            Sprite::from_color(Color::srgb(0.20, 0.7, 0.20), option_size * 1.2),
-            Transform::from_translation(option_position_1.extend(-0.5)),
-            Cursor(0),
-        ));
+    let choices = offered_choices(&context.selected_scene, &context.flags, &locale);
+    let Some(first) = choices.iter().position(|choice| !choice.locked) else {
+        // nothing the player is allowed to pick, don't leave them stuck
+        log::warn!("no choice can be picked in {}", context.selected_scene.id);
+        tmp.set(DatingState::Chilling);
+        return;
+    };
+
+    // placed by choose_move
+    let option_size = Vec2::new(width / 2.0, height / 8.0);
+    commands.spawn((
+        Sprite::from_color(Color::srgb(0.20, 0.7, 0.20), option_size * 1.1),
+        Transform::from_translation(Vec3::new(0.0, 0.0, -0.5)),
+        Cursor(0),
+    ));
+    for (index, choice) in choices.into_iter().enumerate() {
+        let unread = !choice.locked
+            && choice.target.to_lowercase() != "return"
+            && !read_lines.visited(&choice.target);
+        let color = if choice.locked {
+            LOCKED_CHOICE_COLOR
+        } else {
+            Color::srgb(0.20, 0.3, 0.70)
+        };
         commands
             .spawn((
-                Sprite::from_color(Color::srgb(0.20, 0.3, 0.70), option_size),
-                Transform::from_translation(option_position_1.extend(0.0)),
-                ChoiceObj(id.clone()),
+                Sprite::from_color(color, option_size),
+                Transform::default(),
+                Visibility::Hidden,
+                Clickable(index as isize),
+                ChoiceObj(choice.target),
             ))
             .with_children(|builder| {
                 builder.spawn((
-                    Text2d::new(label),
+                    Text2d::new(format!("{}. {}", index + 1, choice.label)),
                     slightly_smaller_text_font.clone(),
                     TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
                     TextBounds::from(option_size * 0.85),
                     Transform::from_translation(Vec3::Z),
                     TextColor(Color::srgb(0.0, 0.0, 0.0)),
                 ));
+                // leads somewhere the player hasn't been yet
+                if unread {
+                    builder.spawn((
+                        Text2d::new(locale.text("choice-new")),
+                        TextFont {
+                            font_size: 18.0,
+                            ..slightly_smaller_text_font.clone()
+                        },
+                        TextColor(Color::srgb(1.0, 0.9, 0.2)),
+                        Transform::from_translation(
+                            (option_size / 2.0 - Vec2::new(30.0, 12.0)).extend(1.0),
+                        ),
+                    ));
+                }
             });
     }
-    context.cursor = 0;
+    context.cursor = first as isize;
+    scroll.0 = scroll_to(0, first);
+}
+
+/// The next choice from `cursor` that can be picked, staying put at either end.
+fn step_choice(choices: &[OfferedChoice], cursor: usize, forward: bool) -> usize {
+    let mut candidates: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new(cursor + 1..choices.len())
+    } else {
+        Box::new((0..cursor).rev())
+    };
+    candidates
+        .find(|&index| !choices[index].locked)
+        .unwrap_or(cursor)
+}
+
+/// The first choice to show so that `cursor` is on screen, moving as little as possible.
+fn scroll_to(first: usize, cursor: usize) -> usize {
+    if cursor < first {
+        cursor
+    } else if cursor >= first + VISIBLE_CHOICES {
+        cursor + 1 - VISIBLE_CHOICES
+    } else {
+        first
+    }
 }
 
 fn choose_move(
-    time: Res<Time>,
+    actions: Res<ButtonInput<Action>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    query: Query<&mut ChoiceObj, With<ChoiceObj>>,
-    mut cursor_query: Query<&mut Transform, With<Cursor>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut pointer: Pointer,
+    mut options: Query<(&Clickable, &mut Transform, &mut Visibility), With<ChoiceObj>>,
+    mut cursor_query: Query<(&mut Transform, &mut Visibility), (With<Cursor>, Without<ChoiceObj>)>,
+    mut scroll: ResMut<ChoiceScroll>,
     mut context: ResMut<DatingContext>,
     mut tmp: ResMut<NextState<DatingState>>,
     windows: Query<&mut Window, With<PrimaryWindow>>,
+    locale: Res<Locale>,
 ) {
-    let down = keyboard_input.just_pressed(KeyCode::KeyS)
-        || keyboard_input.just_pressed(KeyCode::ArrowDown);
-    let up =
-        keyboard_input.just_pressed(KeyCode::KeyW) || keyboard_input.just_pressed(KeyCode::ArrowUp);
-    let confirm = keyboard_input.just_pressed(KeyCode::Enter)
-        || keyboard_input.just_pressed(KeyCode::Space)
-        || keyboard_input.just_pressed(KeyCode::KeyZ);
+    let choices = offered_choices(&context.selected_scene, &context.flags, &locale);
+    if choices.is_empty() {
+        return;
+    }
+
+    let previous = context.cursor;
+    let mut cursor = context.cursor.clamp(0, choices.len() as isize - 1) as usize;
+    let mut confirm = actions.just_pressed(Action::Confirm);
 
-    if up && context.cursor == 1 {
-        context.cursor = 0;
-    } else if down && context.cursor == 0 {
-        context.cursor = 1;
+    if actions.just_pressed(Action::Up) {
+        cursor = step_choice(&choices, cursor, false);
+    } else if actions.just_pressed(Action::Down) {
+        cursor = step_choice(&choices, cursor, true);
     }
 
-    let height = windows.single().resolution.height();
+    // 1 to 9 pick a choice straight away
+    let digits = [
+        (KeyCode::Digit1, KeyCode::Numpad1),
+        (KeyCode::Digit2, KeyCode::Numpad2),
+        (KeyCode::Digit3, KeyCode::Numpad3),
+        (KeyCode::Digit4, KeyCode::Numpad4),
+        (KeyCode::Digit5, KeyCode::Numpad5),
+        (KeyCode::Digit6, KeyCode::Numpad6),
+        (KeyCode::Digit7, KeyCode::Numpad7),
+        (KeyCode::Digit8, KeyCode::Numpad8),
+        (KeyCode::Digit9, KeyCode::Numpad9),
+    ];
+    for (index, (digit, numpad)) in digits.into_iter().enumerate() {
+        if index < choices.len() && keyboard_input.any_just_pressed([digit, numpad]) {
+            cursor = index;
+            confirm = true;
+        }
+    }
 
-    if confirm {
-        if let Some(choices) = context.selected_scene.choice.clone() {
-            if (choices[context.cursor as usize].1).to_lowercase() == "return" {
-                tmp.set(DatingState::Chilling);
-            } else {
-                for scene in context.scenes.clone() {
-                    if scene.id == choices[context.cursor as usize].1 {
-                        context.selected_scene = scene;
-                        tmp.set(DatingState::Talking);
-                        break;
-                    };
-                }
-            }
+    // the wheel scrolls the list without moving the selection
+    let scrolled = wheel.read().map(|event| event.y).sum::<f32>();
+    let last_first = choices.len().saturating_sub(VISIBLE_CHOICES);
+    if scrolled > 0.0 {
+        scroll.0 = scroll.0.saturating_sub(1);
+    } else if scrolled < 0.0 {
+        scroll.0 = (scroll.0 + 1).min(last_first);
+    }
+
+    let mut pointed = cursor as isize;
+    if pointer.select(&mut pointed) {
+        confirm = true;
+    }
+    cursor = pointed as usize;
+
+    if cursor as isize != previous {
+        scroll.0 = scroll_to(scroll.0, cursor);
+    }
+    context.cursor = cursor as isize;
+
+    if confirm && !choices[cursor].locked {
+        context.log(BacklogEntry::Choice(choices[cursor].label.clone()));
+        for (flag, change) in choices[cursor].outcome.clone() {
+            *context.flags.entry(flag).or_insert(0) += change;
+        }
+
+        let target = &choices[cursor].target;
+        if target.to_lowercase() == "return" {
+            tmp.set(DatingState::Chilling);
+        } else if context.set_scene(target) {
+            tmp.set(DatingState::Talking);
+        } else {
+            log::error!("choice leads to missing scene {target}");
         }
     }
 
-    for mut transform in &mut cursor_query {
-        transform.translation.y = match context.cursor {
-            1 => -height / 4.0,
-            _ => height / 4.0,
-        };
+    // lay out the part of the list that fits, centered on screen
+    let height = windows.single().resolution.height();
+    let step = height / 6.0;
+    let shown = choices.len().min(VISIBLE_CHOICES);
+    let top = (shown - 1) as f32 * step / 2.0;
+    let row_y = |index: usize| top - (index as f32 - scroll.0 as f32) * step;
+    let on_screen = |index: usize| {
+        if (scroll.0..scroll.0 + VISIBLE_CHOICES).contains(&index) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+
+    for (clickable, mut transform, mut visibility) in &mut options {
+        let index = clickable.0 as usize;
+        transform.translation.y = row_y(index);
+        *visibility = on_screen(index);
+    }
+    for (mut transform, mut visibility) in &mut cursor_query {
+        transform.translation.y = row_y(cursor);
+        *visibility = on_screen(cursor);
     }
 }
 
 fn talking_action(
     time: Res<Time>,
     mut commands: Commands,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
+    mouse: Res<ButtonInput<MouseButton>>,
     mut new_scene: Local<bool>,
+    mut mode: ResMut<DialogueMode>,
     query: Single<
-        (Entity, &mut TextBox, &mut Text2d, Option<&EmptyScene>),
+        (Entity, &mut TextBox, &mut Typewriter, Option<&EmptyScene>),
         (With<TextBox>, Without<Portrait>, Without<NameBox>),
     >,
     mut name_query: Query<Entity, (With<NameBox>, Without<TextBox>)>,
     mut face_query: Query<Entity, With<Portrait>>,
     mut context: ResMut<DatingContext>,
+    mut read_lines: ResMut<ReadLines>,
+    locale: Res<Locale>,
     asset_server: Res<AssetServer>,
     mut tmp: ResMut<NextState<DatingState>>,
     windows: Query<&mut Window, With<PrimaryWindow>>,
     background: Single<&mut Sprite, With<Background>>,
 ) {
-    let confirm = keyboard_input.just_pressed(KeyCode::Enter)
-        || keyboard_input.just_pressed(KeyCode::Space)
-        || keyboard_input.just_pressed(KeyCode::KeyZ);
-    let escape = keyboard_input.just_pressed(KeyCode::Escape);
+    // clicking anywhere moves the dialogue on
+    let mut confirm =
+        actions.just_pressed(Action::Confirm) || mouse.just_pressed(MouseButton::Left);
+    let escape = actions.just_pressed(Action::Cancel);
+
+    let (entity, mut textbox, mut typewriter, is_empty) = query.into_inner();
+
+    let scene = &context.selected_scene.id;
+    let read = is_empty.is_some() || read_lines.contains(scene, textbox.0);
+    if !read && typewriter.done() {
+        read_lines.insert(scene, Some(textbox.0));
+    } else if is_empty.is_some() && !read_lines.visited(scene) {
+        read_lines.insert(scene, None);
+    }
+    if typewriter.done() && !typewriter.logged && typewriter.length() > 0 {
+        context.log(BacklogEntry::Line {
+            speaker: typewriter.speaker.clone(),
+            text: typewriter.text(),
+        });
+        typewriter.logged = true;
+    }
+
+    if actions.just_pressed(Action::AutoAdvance) {
+        mode.auto = !mode.auto;
+    }
+    if actions.just_pressed(Action::Skip) {
+        mode.skip = !mode.skip;
+    }
+    if mode.skip && !read {
+        mode.skip = false;
+    }
 
-    let (entity, mut textbox, mut text, is_empty) = query.into_inner();
+    // the first press shows the rest of the line, the next one moves on
+    if confirm && !typewriter.done() {
+        typewriter.finish();
+        confirm = false;
+    }
+    if mode.skip {
+        typewriter.finish();
+        confirm = typewriter.finished_for >= SKIP_DELAY;
+    } else if mode.auto && typewriter.finished_for >= AUTO_ADVANCE_DELAY {
+        confirm = true;
+    }
 
     let mut background = background.into_inner();
 
@@ -707,10 +1094,8 @@ fn talking_action(
             *new_scene = false;
         }
 
-        if textbox.0 < context.selected_scene.text.len() {
-            let dialogue = context.selected_scene.text[textbox.0].1.clone();
-
-            *text = Text2d::new(dialogue);
+        if let Some((speaker, dialogue)) = context.selected_scene.line(textbox.0, &locale) {
+            typewriter.start(&dialogue, speaker, &context);
 
             for entity in &mut name_query {
                 commands.entity(entity).despawn_recursive();
@@ -733,6 +1118,9 @@ fn talking_action(
             };
 
             if let Some(new_person) = dbg!(context.selected_scene.text[(*textbox).0].0.clone()) {
+                let name = context
+                    .display_name(&new_person.character, locale.language())
+                    .to_string();
                 let window = windows.single();
                 let width = window.resolution.width();
                 let height = window.resolution.height();
@@ -755,7 +1143,7 @@ fn talking_action(
                     .with_children(|builder| {
                         builder.spawn((
                             TextColor(Color::srgb(0.0, 0.0, 0.0)),
-                            Text2d::new(format!("{:?}", new_person)),
+                            Text2d::new(name),
                             NameBox,
                             slightly_smaller_text_font.clone(),
                             TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
@@ -768,11 +1156,10 @@ fn talking_action(
 
                 //Look at sexy person talking
                 commands.spawn((
-                    get_portrait(
-                        new_person,
+                    context.portrait(
+                        &new_person,
                         Vec2::new(width / 2.0, width / 2.0),
                         &asset_server,
-                        context.flags.clone(),
                     ),
                     Transform::from_translation(
                         Vec2::new(-width / 4.0, -height / 10.0).extend(-0.5),
@@ -790,29 +1177,8 @@ fn talking_action(
             }
 
             // set outcomes
-            if context.selected_scene.outcome.is_some() {
-                println!("Added flag, but not implemented")
-            }
-
-            fn check_cond(
-                flags: &mut HashMap<String, isize>,
-                flag: Option<&String>,
-                threshold: isize,
-            ) -> bool {
-                let Some(flag) = flag else {
-                    return true;
-                };
-
-                let value = *flags.get(flag).unwrap_or(&0);
-
-                if value >= threshold {
-                    return true;
-                }
-
-                if threshold < 0 && value < threshold.abs() {
-                    return true;
-                }
-                return false;
+            for (flag, change) in context.selected_scene.outcome.clone().unwrap_or_default() {
+                *context.flags.entry(flag).or_insert(0) += change;
             }
 
             // if we have an option, start choosing
@@ -822,13 +1188,7 @@ fn talking_action(
             // else, find more dialogue or quit
             else if context.selected_scene.next_scene.len() > 0 {
                 for (cond, next_scene) in context.selected_scene.next_scene.clone() {
-                    let mut passed = true;
-
-                    for (ref flag, threshold) in cond {
-                        passed = passed && check_cond(&mut context.flags, flag.as_ref(), threshold);
-                    }
-
-                    if passed {
+                    if cond_met(&context.flags, &cond) {
                         if next_scene.to_lowercase() == "return" {
                             tmp.set(DatingState::Chilling);
                             break;
@@ -846,11 +1206,21 @@ fn talking_action(
                                     })
                                     .log_components();
                                 textbox.0 = 0;
-                                let dialogue = context.selected_scene.text[0].1.clone();
-                                *text = Text2d::new(dialogue);
+                                if let Some((speaker, dialogue)) =
+                                    context.selected_scene.line(0, &locale)
+                                {
+                                    typewriter.start(&dialogue, speaker, &context);
+                                }
                                 break;
                             };
                         }
+
+                        // only the first branch that passes is taken
+                        if !*new_scene {
+                            log::warn!("no scene called {next_scene}");
+                            tmp.set(DatingState::Chilling);
+                        }
+                        break;
                     }
                 }
             } else {
@@ -860,11 +1230,162 @@ fn talking_action(
     }
 }
 
+/// Whether a flag passes a check: at least `threshold`, or below `-threshold` when it's negative.
+fn check_cond(flags: &HashMap<String, isize>, flag: Option<&String>, threshold: isize) -> bool {
+    let Some(flag) = flag else {
+        return true;
+    };
+
+    let value = *flags.get(flag).unwrap_or(&0);
+    if threshold < 0 {
+        value < threshold.abs()
+    } else {
+        value >= threshold
+    }
+}
+
+fn cond_met(flags: &HashMap<String, isize>, cond: &Cond) -> bool {
+    cond.iter()
+        .all(|(flag, threshold)| check_cond(flags, flag.as_ref(), *threshold))
+}
+
+fn type_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    mut typewriters: Query<(
+        Entity,
+        &mut Typewriter,
+        &TextFont,
+        &TextColor,
+        Option<&Children>,
+    )>,
+    mut spans: Query<&mut TextSpan>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut typewriter, font, color, children) in &mut typewriters {
+        if typewriter.done() {
+            typewriter.finished_for += delta;
+        } else if typewriter.paused_for > 0.0 {
+            typewriter.paused_for -= delta;
+        } else if settings.text_speed <= 0.0 {
+            typewriter.finish();
+        } else {
+            let before = typewriter.shown as usize;
+            typewriter.shown =
+                (typewriter.shown + settings.text_speed * delta).min(typewriter.length() as f32);
+
+            // stop at the next [pause] once it's reached
+            if let Some(&(at, secs)) = typewriter.line.pauses.get(typewriter.next_pause) {
+                if typewriter.shown >= at as f32 {
+                    typewriter.shown = at as f32;
+                    typewriter.paused_for = secs;
+                    typewriter.next_pause += 1;
+                }
+            }
+
+            // every other letter blips, narration stays quiet
+            let typed = typewriter.shown as usize;
+            let blip = typewriter
+                .chars()
+                .enumerate()
+                .take(typed)
+                .skip(before)
+                .any(|(index, c)| index % 2 == 0 && !c.is_whitespace());
+            if let Some(voice) = typewriter.voice.filter(|_| blip) {
+                commands.spawn((
+                    AudioPlayer(pitches.add(Pitch::new(voice, Duration::from_millis(35)))),
+                    PlaybackSettings::DESPAWN.with_volume(Volume::new(0.15)),
+                ));
+            }
+        }
+
+        // one span per styled run, each showing as much of it as is typed
+        let fresh = std::mem::take(&mut typewriter.fresh);
+        let mut remaining = typewriter.shown as usize;
+        let visible = typewriter.line.spans.iter().map(|(text, style)| {
+            let count = text.chars().count().min(remaining);
+            remaining -= count;
+            (text.chars().take(count).collect::<String>(), style)
+        });
+
+        if fresh {
+            commands
+                .entity(entity)
+                .despawn_descendants()
+                .with_children(|builder| {
+                    for (text, style) in visible {
+                        builder.spawn((
+                            TextSpan::new(text),
+                            span_font(font, style),
+                            TextColor(style.color.unwrap_or(color.0)),
+                        ));
+                    }
+                });
+        } else {
+            for (&child, (text, _)) in children.into_iter().flatten().zip(visible) {
+                if let Ok(mut span) = spans.get_mut(child) {
+                    if span.0 != text {
+                        span.0 = text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn span_font(font: &TextFont, style: &Style) -> TextFont {
+    if style.emphasis {
+        TextFont {
+            font_size: font.font_size * EMPHASIS_SCALE,
+            ..font.clone()
+        }
+    } else {
+        font.clone()
+    }
+}
+
+fn shake_text(time: Res<Time>, mut typewriters: Query<(&Typewriter, &mut Transform)>) {
+    let t = time.elapsed_secs();
+    for (typewriter, mut transform) in &mut typewriters {
+        let offset = if typewriter.shaking() {
+            Vec2::new((t * 53.0).sin(), (t * 71.0).cos()) * SHAKE
+        } else {
+            Vec2::ZERO
+        };
+        transform.translation = offset.extend(transform.translation.z);
+    }
+}
+
+fn show_dialogue_mode(
+    mode: Res<DialogueMode>,
+    locale: Res<Locale>,
+    mut indicators: Query<&mut Text2d, Added<ModeIndicator>>,
+    mut all_indicators: Query<&mut Text2d, With<ModeIndicator>>,
+) {
+    let label = match (mode.auto, mode.skip) {
+        (_, true) => locale.text("dialogue-skip"),
+        (true, false) => locale.text("dialogue-auto"),
+        (false, false) => "",
+    };
+
+    if mode.is_changed() || locale.is_changed() {
+        for mut text in &mut all_indicators {
+            text.0 = label.to_string();
+        }
+    } else {
+        for mut text in &mut indicators {
+            text.0 = label.to_string();
+        }
+    }
+}
+
 fn format_cond(cond: &Cond) {
     for (flag, threshold) in cond {
         if flag.is_none() {
             println!("(always true)");
-        } else if *threshold > 0 {
+        } else if *threshold >= 0 {
             println!("{} >= {threshold}", flag.as_deref().unwrap_or("null"),)
         } else {
             println!(
@@ -876,22 +1397,67 @@ fn format_cond(cond: &Cond) {
     }
 }
 
-fn follow_mouse(
-    q_windows: Query<&Window, With<PrimaryWindow>>,
-    mut transform: Query<&mut Transform, With<FollowsMouse>>,
-) {
-    let Some(position) = q_windows.single().cursor_position() else {
-        return;
-    };
+/// The mouse, for pointing at [`Clickable`] sprites.
+#[derive(SystemParam)]
+struct Pointer<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    buttons: Res<'w, ButtonInput<MouseButton>>,
+    moved: EventReader<'w, 's, CursorMoved>,
+    clickables: Query<
+        'w,
+        's,
+        (
+            &'static Clickable,
+            &'static GlobalTransform,
+            &'static Sprite,
+            &'static InheritedVisibility,
+        ),
+    >,
+}
+
+impl Pointer<'_, '_> {
+    /// Moves `cursor` to what the mouse is moved onto or clicks, returning whether it clicked.
+    ///
+    /// A mouse that isn't moving leaves the cursor alone, so it doesn't fight the keyboard.
+    fn select(&mut self, cursor: &mut isize) -> bool {
+        let moved = self.moved.read().count() > 0;
+        let clicked = self.buttons.just_pressed(MouseButton::Left);
+        let Some(target) = self.pointed_at() else {
+            return false;
+        };
+
+        if moved || clicked {
+            *cursor = target;
+        }
+        clicked
+    }
 
-    for mut transform in &mut transform {
-        transform.translation = position.extend(0.0);
+    fn pointed_at(&self) -> Option<isize> {
+        let window = self.windows.get_single().ok()?;
+        let (camera, camera_transform) = self.cameras.get_single().ok()?;
+        let position = camera
+            .viewport_to_world_2d(camera_transform, window.cursor_position()?)
+            .ok()?;
+
+        self.clickables
+            .iter()
+            .filter(|(.., visibility)| visibility.get())
+            .find(|(_, transform, sprite, _)| {
+                let half_size = sprite.custom_size.unwrap_or_default() / 2.0;
+                (transform.translation().truncate() - position)
+                    .abs()
+                    .cmple(half_size)
+                    .all()
+            })
+            .map(|(clickable, ..)| clickable.0)
     }
 }
 
 fn cursor_action(
     time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
+    mut pointer: Pointer,
     mut query: Query<&mut Transform, With<Cursor>>,
     mut context: ResMut<DatingContext>,
     mut tmp: ResMut<NextState<DatingState>>,
@@ -901,57 +1467,182 @@ fn cursor_action(
     // Consider changing font-size instead of scaling the transform. Scaling a Text2D will scale the
     // rendered quad, resulting in a pixellated look.
 
-    let left = keyboard_input.just_pressed(KeyCode::KeyA)
-        || keyboard_input.just_pressed(KeyCode::ArrowLeft);
-    let right = keyboard_input.just_pressed(KeyCode::KeyD)
-        || keyboard_input.just_pressed(KeyCode::ArrowRight);
-    let up =
-        keyboard_input.just_pressed(KeyCode::KeyW) || keyboard_input.just_pressed(KeyCode::ArrowUp);
-    let down = keyboard_input.just_pressed(KeyCode::KeyS)
-        || keyboard_input.just_pressed(KeyCode::ArrowDown);
-    let confirm = keyboard_input.just_pressed(KeyCode::Enter)
-        || keyboard_input.just_pressed(KeyCode::Space)
-        || keyboard_input.just_pressed(KeyCode::KeyZ);
+    let left = actions.just_pressed(Action::Left);
+    let right = actions.just_pressed(Action::Right);
+    let up = actions.just_pressed(Action::Up);
+    let down = actions.just_pressed(Action::Down);
+    let clicked = pointer.select(&mut context.cursor);
+    let confirm = actions.just_pressed(Action::Confirm) || clicked;
+
+    let crew = context
+        .crew()
+        .filter_map(|character| character.init_scene.clone())
+        .collect::<Vec<_>>();
+    let last = crew.len() as isize - 1;
 
     if confirm {
-        if context.cursor == -5 {
+        if context.cursor == EXIT_CURSOR {
             tmp.set(DatingState::Noting);
             tmp_super.set(GameState::Explore);
-        } else {
-            let talk_key = context.all_characters[(context.cursor + 3) as usize]
-                .current_dialogue
-                .clone();
-
-            for scene in context.scenes.clone() {
-                if scene.id == talk_key {
-                    context.selected_scene = scene;
-                    break;
-                };
-            }
+        } else if let Some(talk_key) = usize::try_from(context.cursor)
+            .ok()
+            .and_then(|index| crew.get(index))
+        {
+            context.set_scene(talk_key);
             println!("set talking");
             tmp.set(DatingState::Talking);
         }
     }
 
-    if right && context.cursor < 3 && context.cursor != -5 {
+    if right && context.cursor < last && context.cursor != EXIT_CURSOR {
         context.cursor += 1
-    } else if left && context.cursor > -3 {
+    } else if left && context.cursor > 0 {
         context.cursor -= 1
-    } else if up && context.cursor == -5 {
-        context.cursor = 0;
-    } else if down && context.cursor != -5 {
-        context.cursor = -5;
+    } else if up && context.cursor == EXIT_CURSOR {
+        context.cursor = last / 2;
+    } else if down && context.cursor != EXIT_CURSOR {
+        context.cursor = EXIT_CURSOR;
     }
 
     for mut transform in &mut query {
         let width = windows.single().resolution.width();
         let height = windows.single().resolution.height();
-        if context.cursor != -5 {
+        if context.cursor != EXIT_CURSOR {
+            let (positions, _) = crew_layout(crew.len(), width);
             transform.translation.y = 250.0;
-            transform.translation.x = (context.cursor as f32) * width / 7.5;
+            transform.translation.x = positions
+                .get(context.cursor as usize)
+                .copied()
+                .unwrap_or_default();
         } else {
             transform.translation.y = -height / 3.0;
             transform.translation.x = 0.0;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choice_list_skips_locked_choices_and_scrolls() {
+        let choice = |locked| OfferedChoice {
+            label: String::new(),
+            target: String::new(),
+            locked,
+            outcome: vec![],
+        };
+        let choices = [choice(false), choice(true), choice(false), choice(true)];
+
+        assert_eq!(step_choice(&choices, 0, true), 2);
+        assert_eq!(step_choice(&choices, 2, true), 2);
+        assert_eq!(step_choice(&choices, 2, false), 0);
+
+        assert_eq!(scroll_to(0, 3), 0);
+        assert_eq!(scroll_to(0, 5), 2);
+        assert_eq!(scroll_to(2, 1), 1);
+    }
+
+    #[test]
+    fn conditional_choices_are_hidden_or_locked() {
+        let scene: DatingScene = serde_json::from_str(
+            r#"{
+                "id": "Test",
+                "text": [],
+                "choice": [
+                    ["Leave.", "return"],
+                    {"label": "Offer the coal", "scene": "Coal", "condition": [["CoalCollected", 1]]},
+                    {"label": "Fix the engine", "scene": "Engine", "condition": [["Parts", 2]],
+                     "locked": "needs parts", "outcome": [["Favor", 1]]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut flags = HashMap::new();
+        let offered = offered_choices(&scene, &flags, &Locale::default());
+        assert_eq!(offered.len(), 2);
+        assert_eq!(offered[1].label, "Fix the engine (needs parts)");
+        assert!(offered[1].locked);
+
+        flags.insert("CoalCollected".to_string(), 1);
+        flags.insert("Parts".to_string(), 2);
+        let offered = offered_choices(&scene, &flags, &Locale::default());
+        assert_eq!(offered.len(), 3);
+        assert_eq!(offered[1].target, "Coal");
+        assert!(!offered[2].locked);
+        assert_eq!(offered[2].outcome, vec![("Favor".to_string(), 1)]);
+    }
+
+    #[test]
+    fn translations_report_missing_and_stale_text() {
+        let scenes: Vec<DatingScene> = serde_json::from_str(
+            r#"[{
+                "id": "Test",
+                "text": [[null, "One."], [null, "Two."]],
+                "choice": [["Leave.", "return"]]
+            }]"#,
+        )
+        .unwrap();
+        let translation = serde_json::from_str(
+            r#"{
+                "Test": {"lines": {"0": "Eins.", "2": "Drei."}, "choices": {"0": "Gehen."}, "locked": {"0": "nein"}},
+                "Gone": {"lines": {"0": "[em]Weg."}}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            untranslated_text(&scenes, &translation),
+            [
+                "Test line 1",
+                "Gone: no such scene",
+                "Test line 2: no such line",
+                "Test choice 0: no such locked reason",
+                "Gone line 0: [em] is never closed",
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_thresholds_mean_below() {
+        let flags = HashMap::from([("Evening".to_string(), 1)]);
+        let evening = Some("Evening".to_string());
+
+        assert!(check_cond(&flags, evening.as_ref(), 1));
+        assert!(!check_cond(&flags, evening.as_ref(), -1));
+        assert!(check_cond(&flags, evening.as_ref(), -2));
+        assert!(check_cond(&flags, None, 5));
+    }
+
+    #[test]
+    fn joe_stops_asking_once_the_filtration_is_fixed() {
+        let characters = load::load_characters();
+        let scenes = load::load_scenes(&characters);
+        let joe = scenes.iter().find(|scene| scene.id == "JoeInit").unwrap();
+        let next = |flags: &[(&str, isize)]| {
+            let flags = flags
+                .iter()
+                .map(|(flag, value)| (flag.to_string(), *value))
+                .collect();
+            joe.next_scene
+                .iter()
+                .find(|(cond, _)| cond_met(&flags, cond))
+                .map(|(_, id)| id.as_str())
+        };
+
+        // read as "at least -1" these would pass whatever the flags, and Joe
+        // would ask for the air to be fixed every evening
+        assert_eq!(next(&[("Evening", 1)]), Some("JoeFixAir"));
+        assert_eq!(
+            next(&[("Evening", 1), ("FiltrationFixed", 1)]),
+            Some("JoeRebuff")
+        );
+        assert_eq!(next(&[("Day", 2)]), Some("JoeCatEater"));
+        assert_eq!(
+            next(&[("Day", 2), ("TalkedAboutCat", 1)]),
+            Some("JoeRebuff")
+        );
+    }
+}
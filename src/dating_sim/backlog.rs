@@ -0,0 +1,267 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{roster::Speaker, DatingContext, DatingState};
+use crate::{despawn_screen, input::Action, locale::Locale};
+
+// older entries are dropped past this
+const MAX_BACKLOG: usize = 500;
+// entries on screen at once
+const BACKLOG_ROWS: usize = 6;
+const PORTRAIT_SIZE: f32 = 80.0;
+
+/// Something that happened in dialogue, kept so the player can read back through it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BacklogEntry {
+    Line {
+        speaker: Option<Speaker>,
+        text: String,
+    },
+    /// The label of a choice the player picked.
+    Choice(String),
+}
+
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(DatingState = DatingState::Talking)]
+pub(crate) enum BacklogState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// How many entries are hidden below the ones on screen, 0 being the newest.
+#[derive(Resource, Default)]
+struct BacklogScroll(usize);
+
+#[derive(Component)]
+struct OnBacklog;
+
+#[derive(Component)]
+struct BacklogRows;
+
+impl DatingContext {
+    pub(super) fn log(&mut self, entry: BacklogEntry) {
+        self.backlog.push(entry);
+        if self.backlog.len() > MAX_BACKLOG {
+            let excess = self.backlog.len() - MAX_BACKLOG;
+            self.backlog.drain(..excess);
+        }
+    }
+}
+
+pub fn backlog_plugin(app: &mut App) {
+    app.add_sub_state::<BacklogState>()
+        .init_resource::<BacklogScroll>()
+        .add_systems(Update, open_backlog.run_if(in_state(BacklogState::Closed)))
+        .add_systems(OnEnter(BacklogState::Open), spawn_backlog)
+        .add_systems(OnExit(BacklogState::Open), despawn_screen::<OnBacklog>)
+        .add_systems(
+            Update,
+            (backlog_input, draw_backlog)
+                .chain()
+                .run_if(in_state(BacklogState::Open)),
+        );
+}
+
+fn open_backlog(
+    actions: Res<ButtonInput<Action>>,
+    mut wheel: EventReader<MouseWheel>,
+    context: Res<DatingContext>,
+    mut next_state: ResMut<NextState<BacklogState>>,
+) {
+    let scrolled_up = wheel.read().map(|event| event.y).sum::<f32>() > 0.0;
+    if (actions.just_pressed(Action::Backlog) || scrolled_up) && !context.backlog.is_empty() {
+        next_state.set(BacklogState::Open);
+    }
+}
+
+fn spawn_backlog(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut scroll: ResMut<BacklogScroll>,
+) {
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+    scroll.0 = 0;
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            GlobalZIndex(10),
+            OnBacklog,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new(locale.text("backlog-title")),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 50.0,
+                    ..default()
+                },
+            ));
+            builder.spawn((
+                Node {
+                    width: Val::Percent(80.),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                BacklogRows,
+            ));
+        });
+}
+
+/// Where the backlog scrolls to from `scroll`, or `None` when scrolling down past the newest entry closes it.
+fn scroll_backlog(scroll: usize, len: usize, up: bool) -> Option<usize> {
+    if up {
+        Some((scroll + 1).min(len.saturating_sub(BACKLOG_ROWS)))
+    } else {
+        scroll.checked_sub(1)
+    }
+}
+
+fn backlog_input(
+    actions: Res<ButtonInput<Action>>,
+    mut wheel: EventReader<MouseWheel>,
+    context: Res<DatingContext>,
+    mut scroll: ResMut<BacklogScroll>,
+    mut next_state: ResMut<NextState<BacklogState>>,
+) {
+    if actions.any_just_pressed([Action::Cancel, Action::Backlog]) {
+        next_state.set(BacklogState::Closed);
+        return;
+    }
+
+    let scrolled = wheel.read().map(|event| event.y).sum::<f32>();
+    let up = if actions.just_pressed(Action::Up) || scrolled > 0.0 {
+        true
+    } else if actions.just_pressed(Action::Down) || scrolled < 0.0 {
+        false
+    } else {
+        return;
+    };
+
+    match scroll_backlog(scroll.0, context.backlog.len(), up) {
+        Some(to) if to != scroll.0 => scroll.0 = to,
+        Some(_) => {}
+        None => next_state.set(BacklogState::Closed),
+    }
+}
+
+fn draw_backlog(
+    mut commands: Commands,
+    scroll: Res<BacklogScroll>,
+    context: Res<DatingContext>,
+    locale: Res<Locale>,
+    asset_server: Res<AssetServer>,
+    rows: Query<Entity, With<BacklogRows>>,
+    added: Query<(), Added<BacklogRows>>,
+) {
+    if !scroll.is_changed() && added.is_empty() {
+        return;
+    }
+    let Ok(rows) = rows.get_single() else {
+        return;
+    };
+
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+    let text_font = TextFont {
+        font,
+        font_size: 22.0,
+        ..default()
+    };
+
+    let end = context.backlog.len().saturating_sub(scroll.0);
+    let start = end.saturating_sub(BACKLOG_ROWS);
+
+    commands
+        .entity(rows)
+        .despawn_descendants()
+        .with_children(|builder| {
+            for entry in &context.backlog[start..end] {
+                builder
+                    .spawn(Node {
+                        column_gap: Val::Px(20.),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    })
+                    .with_children(|builder| match entry {
+                        BacklogEntry::Line { speaker, text } => {
+                            let portrait = Node {
+                                width: Val::Px(PORTRAIT_SIZE),
+                                height: Val::Px(PORTRAIT_SIZE),
+                                flex_shrink: 0.,
+                                ..default()
+                            };
+                            if let Some(speaker) = speaker {
+                                let sprite = context.portrait(
+                                    speaker,
+                                    Vec2::splat(PORTRAIT_SIZE),
+                                    &asset_server,
+                                );
+                                builder.spawn((
+                                    portrait,
+                                    ImageNode::new(sprite.image).with_color(sprite.color),
+                                ));
+                            } else {
+                                // keeps narration lined up with what's said
+                                builder.spawn(portrait);
+                            }
+
+                            builder
+                                .spawn(Node {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                })
+                                .with_children(|builder| {
+                                    if let Some(speaker) = speaker {
+                                        builder.spawn((
+                                            Text::new(context.display_name(
+                                                &speaker.character,
+                                                locale.language(),
+                                            )),
+                                            text_font.clone(),
+                                            TextColor(Color::srgb(1.0, 0.85, 0.4)),
+                                        ));
+                                    }
+                                    builder.spawn((Text::new(text.clone()), text_font.clone()));
+                                });
+                        }
+                        BacklogEntry::Choice(label) => {
+                            builder.spawn((
+                                Node {
+                                    margin: UiRect::left(Val::Px(PORTRAIT_SIZE + 20.)),
+                                    ..default()
+                                },
+                                Text::new(format!("> {label}")),
+                                text_font.clone(),
+                                TextColor(Color::srgb(0.4, 0.9, 0.4)),
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolling_stops_at_the_oldest_and_closes_past_the_newest() {
+        assert_eq!(scroll_backlog(0, 10, true), Some(1));
+        assert_eq!(scroll_backlog(4, 10, true), Some(4));
+        assert_eq!(scroll_backlog(0, 3, true), Some(0));
+        assert_eq!(scroll_backlog(2, 10, false), Some(1));
+        assert_eq!(scroll_backlog(0, 10, false), None);
+    }
+}
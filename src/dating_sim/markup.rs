@@ -0,0 +1,279 @@
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use anyhow::{anyhow, bail, Context};
+use bevy::prelude::*;
+
+use super::DatingScene;
+use crate::locale::SceneTranslation;
+
+// seconds a bare [pause] waits
+const DEFAULT_PAUSE: f32 = 0.5;
+
+/// How a run of text in a line looks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub emphasis: bool,
+    pub color: Option<Color>,
+    pub shake: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tag {
+    Em,
+    Color,
+    Shake,
+}
+
+impl Tag {
+    fn name(self) -> &'static str {
+        match self {
+            Tag::Em => "em",
+            Tag::Color => "color",
+            Tag::Shake => "shake",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Text(String, Style),
+    Flag(String, Style),
+    Pause(f32),
+}
+
+/// A dialogue line with its markup parsed.
+///
+/// - `[em]...[/em]` for emphasis
+/// - `[color=red]...[/color]`, by name or as `#rrggbb`
+/// - `[shake]...[/shake]`
+/// - `[pause=0.8]` stops the typing for that many seconds, `[pause]` for half a second
+/// - `{Flag}` for the value of a flag, or `{Captain}` for the player's name
+/// - `[[` and `{{` for a plain `[` and `{`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Markup(Vec<Piece>);
+
+/// A line ready to be typed out: styled runs of text, and pauses by how many characters come before them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rendered {
+    pub spans: Vec<(String, Style)>,
+    pub pauses: Vec<(usize, f32)>,
+}
+
+impl Markup {
+    pub fn parse(line: &str) -> anyhow::Result<Markup> {
+        let mut pieces = vec![];
+        // the tags still open, with the style from before each one
+        let mut open: Vec<(Tag, Style)> = vec![];
+        let mut style = Style::default();
+        let mut text = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '[' | '{' if chars.peek() == Some(&c) => {
+                    chars.next();
+                    text.push(c);
+                }
+                '[' => {
+                    let tag = take_until(&mut chars, ']')?;
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text), style));
+                    }
+
+                    let (name, value) = match tag.split_once('=') {
+                        Some((name, value)) => (name, Some(value)),
+                        None => (tag.as_str(), None),
+                    };
+                    match (name, value) {
+                        ("em", None) => {
+                            open.push((Tag::Em, style));
+                            style.emphasis = true;
+                        }
+                        ("shake", None) => {
+                            open.push((Tag::Shake, style));
+                            style.shake = true;
+                        }
+                        ("color", Some(color)) => {
+                            open.push((Tag::Color, style));
+                            style.color = Some(parse_color(color)?);
+                        }
+                        ("pause", None) => pieces.push(Piece::Pause(DEFAULT_PAUSE)),
+                        ("pause", Some(secs)) => {
+                            let secs = secs
+                                .parse::<f32>()
+                                .ok()
+                                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                                .with_context(|| format!("bad pause length {secs:?}"))?;
+                            pieces.push(Piece::Pause(secs));
+                        }
+                        ("/em" | "/color" | "/shake", None) => match open.pop() {
+                            Some((tag, before)) if tag.name() == &name[1..] => style = before,
+                            Some((tag, _)) => {
+                                bail!("[{name}] found while [{}] is open", tag.name())
+                            }
+                            None => bail!("[{name}] closes nothing"),
+                        },
+                        _ => bail!("unknown tag [{tag}]"),
+                    }
+                }
+                '{' => {
+                    let flag = take_until(&mut chars, '}')?;
+                    if flag.is_empty() {
+                        bail!("empty flag {{}}");
+                    }
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text), style));
+                    }
+                    pieces.push(Piece::Flag(flag, style));
+                }
+                c => text.push(c),
+            }
+        }
+
+        if let Some((tag, _)) = open.last() {
+            bail!("[{}] is never closed", tag.name());
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text, style));
+        }
+        Ok(Markup(pieces))
+    }
+
+    /// The line as it was written, for when its markup is broken.
+    pub fn plain(line: &str) -> Markup {
+        Markup(vec![Piece::Text(line.to_string(), Style::default())])
+    }
+
+    /// Lays the line out, with `variable` giving what each `{name}` stands for.
+    pub fn render(&self, variable: impl Fn(&str) -> String) -> Rendered {
+        let mut rendered = Rendered::default();
+        let mut length = 0;
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text, style) => {
+                    length += text.chars().count();
+                    rendered.spans.push((text.clone(), *style));
+                }
+                Piece::Flag(flag, style) => {
+                    let value = variable(flag);
+                    length += value.chars().count();
+                    rendered.spans.push((value, *style));
+                }
+                Piece::Pause(secs) => rendered.pauses.push((length, *secs)),
+            }
+        }
+        rendered
+    }
+}
+
+fn take_until(chars: &mut Peekable<Chars>, end: char) -> anyhow::Result<String> {
+    let mut taken = String::new();
+    for c in chars.by_ref() {
+        if c == end {
+            return Ok(taken);
+        }
+        taken.push(c);
+    }
+    bail!("missing '{end}'")
+}
+
+// darker than usual so they stay readable on the textbox
+fn parse_color(name: &str) -> anyhow::Result<Color> {
+    Ok(match name {
+        "red" => Color::srgb(0.75, 0.1, 0.1),
+        "green" => Color::srgb(0.1, 0.5, 0.1),
+        "blue" => Color::srgb(0.1, 0.2, 0.75),
+        "yellow" => Color::srgb(0.7, 0.55, 0.0),
+        "purple" => Color::srgb(0.5, 0.1, 0.6),
+        "grey" | "gray" => Color::srgb(0.45, 0.45, 0.45),
+        _ if name.starts_with('#') => Srgba::hex(name)
+            .map(Color::from)
+            .map_err(|_| anyhow!("bad color {name:?}"))?,
+        _ => bail!("unknown color {name:?}"),
+    })
+}
+
+/// Every line in `scenes` whose markup doesn't parse, saying where it is.
+pub fn markup_errors(scenes: &[DatingScene]) -> Vec<String> {
+    scenes
+        .iter()
+        .flat_map(|scene| {
+            scene
+                .text
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, (_, line))| {
+                    let error = Markup::parse(line).err()?;
+                    Some(format!("{} line {index}: {error}", scene.id))
+                })
+        })
+        .collect()
+}
+
+/// Every translated line that wouldn't parse, like [`markup_errors`] for a translation.
+pub fn translation_markup_errors(translation: &HashMap<String, SceneTranslation>) -> Vec<String> {
+    let mut errors = translation
+        .iter()
+        .flat_map(|(id, scene)| {
+            scene.lines.iter().filter_map(move |(index, line)| {
+                let error = Markup::parse(line).err()?;
+                Some(format!("{id} line {index}: {error}"))
+            })
+        })
+        .collect::<Vec<_>>();
+    errors.sort();
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_becomes_spans_and_pauses() {
+        let markup =
+            Markup::parse("We have {Coal}[pause=1] [em][color=red]coal[/color]![/em] [[ok]")
+                .unwrap();
+        let rendered = markup.render(|name| match name {
+            "Coal" => "12".to_string(),
+            _ => "0".to_string(),
+        });
+
+        let red = Some(parse_color("red").unwrap());
+        let texts = rendered
+            .spans
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["We have ", "12", " ", "coal", "!", " [ok]"]);
+        assert_eq!(rendered.spans[3].1.color, red);
+        assert!(rendered.spans[3].1.emphasis && rendered.spans[4].1.emphasis);
+        assert_eq!(rendered.spans[4].1.color, None);
+        assert_eq!(rendered.pauses, [(10, 1.0)]);
+    }
+
+    #[test]
+    fn broken_markup_is_rejected() {
+        for line in [
+            "[em]never closed",
+            "[em][shake]crossed[/em][/shake]",
+            "[/em]",
+            "[wobble]",
+            "[color=mauve]x[/color]",
+            "[pause=soon]",
+            "{Coal",
+            "{}",
+        ] {
+            assert!(Markup::parse(line).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn shipped_scenes_have_valid_markup() {
+        let characters = crate::load::load_characters();
+        assert_eq!(
+            markup_errors(&crate::load::load_scenes(&characters)),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+
+use super::{DatingContext, DatingState};
+use crate::{despawn_screen, locale::Locale};
+
+/// What the captain is called if the player doesn't pick a name.
+pub const DEFAULT_CAPTAIN: &str = "Captain";
+const MAX_NAME: usize = 16;
+
+#[derive(Component)]
+struct OnNaming;
+
+#[derive(Component)]
+struct NameEntry;
+
+pub fn naming_plugin(app: &mut App) {
+    app.add_systems(OnEnter(DatingState::Naming), spawn_naming)
+        .add_systems(Update, type_name.run_if(in_state(DatingState::Naming)))
+        .add_systems(OnExit(DatingState::Naming), despawn_screen::<OnNaming>);
+}
+
+fn spawn_naming(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut context: ResMut<DatingContext>,
+    mut typed: ResMut<Events<KeyboardInput>>,
+) {
+    // the key that started the game shouldn't end up in the name
+    typed.clear();
+    context.captain.clear();
+
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            OnNaming,
+        ))
+        .with_children(|builder| {
+            builder.spawn((Text::new(locale.text("naming-question")), text_font(40.0)));
+            builder.spawn((Text::new("_"), text_font(50.0), NameEntry));
+            builder.spawn((
+                Text::new(locale.text("naming-confirm")),
+                text_font(22.0),
+                TextColor(Color::srgb(0.6, 0.6, 0.6)),
+            ));
+        });
+}
+
+fn type_name(
+    mut typed: EventReader<KeyboardInput>,
+    gamepads: Query<&Gamepad>,
+    mut context: ResMut<DatingContext>,
+    mut entry: Single<&mut Text, With<NameEntry>>,
+    mut tmp: ResMut<NextState<DatingState>>,
+) {
+    let mut confirm = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+
+    for event in typed.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        let text = match &event.logical_key {
+            Key::Enter => {
+                confirm = true;
+                continue;
+            }
+            Key::Backspace => {
+                context.captain.pop();
+                continue;
+            }
+            Key::Space => " ",
+            Key::Character(text) => text.as_str(),
+            _ => continue,
+        };
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if context.captain.chars().count() < MAX_NAME {
+                context.captain.push(c);
+            }
+        }
+    }
+
+    if confirm {
+        let name = context.captain.trim().to_string();
+        context.captain = if name.is_empty() {
+            DEFAULT_CAPTAIN.to_string()
+        } else {
+            name
+        };
+        context.set_scene("Day1Morning");
+        context.initialized = true;
+        tmp.set(DatingState::Talking);
+    }
+
+    let shown = format!("{}_", context.captain);
+    if entry.0 != shown {
+        entry.0 = shown;
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{DatingState, SceneID};
+
+/// Every line the player has seen, by scene and line index, kept across saves and playthroughs.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct ReadLines(HashMap<SceneID, HashSet<usize>>);
+
+impl ReadLines {
+    fn path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("DeeperAndDeeper")
+            .join("read.json")
+    }
+
+    pub fn load() -> ReadLines {
+        let path = ReadLines::path();
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::error!("could not read {}: {e}", path.display());
+                ReadLines::default()
+            }),
+            Err(_) => ReadLines::default(),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = ReadLines::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn contains(&self, scene: &str, line: usize) -> bool {
+        self.0.get(scene).is_some_and(|lines| lines.contains(&line))
+    }
+
+    /// Marks `scene` as visited, and `line` of it as read if there is one.
+    pub fn insert(&mut self, scene: &str, line: Option<usize>) {
+        let lines = self.0.entry(scene.to_string()).or_default();
+        lines.extend(line);
+    }
+
+    /// Whether the player has been to `scene` before.
+    pub fn visited(&self, scene: &str) -> bool {
+        self.0.contains_key(scene)
+    }
+}
+
+pub fn read_lines_plugin(app: &mut App) {
+    app.insert_resource(ReadLines::load())
+        .add_systems(
+            OnExit(DatingState::Talking),
+            save_read_lines.run_if(resource_changed::<ReadLines>),
+        )
+        // quitting from the backlog or the pause menu never leaves the dialogue
+        .add_systems(Last, save_read_lines.run_if(on_event::<AppExit>));
+}
+
+pub fn save_read_lines(read_lines: Res<ReadLines>) {
+    if let Err(e) = read_lines.save() {
+        log::error!("could not save read lines: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_remembered_per_scene() {
+        let mut read_lines = ReadLines::default();
+        read_lines.insert("Day1Morning", Some(2));
+        read_lines.insert("Empty", None);
+
+        let json = serde_json::to_string(&read_lines).unwrap();
+        let read_lines: ReadLines = serde_json::from_str(&json).unwrap();
+
+        assert!(read_lines.contains("Day1Morning", 2));
+        assert!(!read_lines.contains("Day1Morning", 0));
+        assert!(read_lines.visited("Empty"));
+        assert!(!read_lines.visited("Day1Evening"));
+    }
+}
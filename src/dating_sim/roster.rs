@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{DatingContext, DatingScene, Flag, SceneID};
+use crate::locale::Localized;
+
+// the expression used when a line doesn't ask for one, or asks for one that's missing
+const NEUTRAL: &str = "neutral";
+
+pub type CharacterId = String;
+
+/// Someone who can speak in a scene, as listed in `assets/characters.json`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Character {
+    pub id: CharacterId,
+    pub name: Localized,
+    /// Stands for the player, and goes by the captain's name.
+    #[serde(default)]
+    pub player: bool,
+    /// Portrait files in `assets/Portraits`, by expression.
+    pub portraits: HashMap<String, String>,
+    /// Where talking to them on the ship starts. Only those with one can be picked there.
+    #[serde(default)]
+    pub init_scene: Option<SceneID>,
+    /// Set to 1 once they are dead.
+    #[serde(default)]
+    pub death_flag: Option<Flag>,
+    /// Their scenes, in `assets/Scenes`.
+    #[serde(default)]
+    pub scene_file: Option<String>,
+    /// The pitch of the blips their lines make, in Hz.
+    pub voice: f32,
+}
+
+/// Who says a line, and how they look saying it: `"Joe"` or `"Joe:angry"`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub struct Speaker {
+    pub character: CharacterId,
+    pub expression: Option<String>,
+}
+
+impl From<String> for Speaker {
+    fn from(value: String) -> Self {
+        match value.split_once(':') {
+            Some((character, expression)) => Speaker {
+                character: character.to_string(),
+                expression: Some(expression.to_string()),
+            },
+            None => Speaker {
+                character: value,
+                expression: None,
+            },
+        }
+    }
+}
+
+impl From<Speaker> for String {
+    fn from(speaker: Speaker) -> Self {
+        speaker.to_string()
+    }
+}
+
+impl fmt::Display for Speaker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.character)?;
+        if let Some(expression) = &self.expression {
+            write!(f, ":{expression}")?;
+        }
+        Ok(())
+    }
+}
+
+impl DatingContext {
+    pub(super) fn character(&self, id: &str) -> Option<&Character> {
+        self.characters.iter().find(|character| character.id == id)
+    }
+
+    /// The characters the player can go and talk to on the ship, in order.
+    pub(super) fn crew(&self) -> impl Iterator<Item = &Character> {
+        self.characters
+            .iter()
+            .filter(|character| character.init_scene.is_some())
+    }
+
+    pub(super) fn display_name<'a>(&'a self, id: &'a str, language: &str) -> &'a str {
+        match self.character(id) {
+            Some(character) if character.player => &self.captain,
+            Some(character) => character.name.get(language),
+            None => id,
+        }
+    }
+
+    /// The portrait of `speaker`, falling back to their neutral one. The dead are a dark silhouette.
+    pub(super) fn portrait(
+        &self,
+        speaker: &Speaker,
+        size: Vec2,
+        asset_server: &AssetServer,
+    ) -> Sprite {
+        let character = self.character(&speaker.character);
+        let dead = character
+            .and_then(|character| character.death_flag.as_ref())
+            .is_some_and(|flag| self.get_flag(flag) == 1);
+        let file = character.and_then(|character| {
+            speaker
+                .expression
+                .as_ref()
+                .and_then(|expression| character.portraits.get(expression))
+                .or_else(|| character.portraits.get(NEUTRAL))
+        });
+
+        Sprite {
+            custom_size: Some(size),
+            image: file
+                .map(|file| asset_server.load(format!("Portraits/{file}")))
+                .unwrap_or_default(),
+            color: if dead {
+                Color::srgba(0.0, 0.0, 0.0, 0.8)
+            } else {
+                Color::WHITE
+            },
+            ..default()
+        }
+    }
+}
+
+/// Lines in `scenes` spoken by someone not in `characters`, saying where they are.
+pub fn unknown_speakers(scenes: &[DatingScene], characters: &[Character]) -> Vec<String> {
+    speakers(scenes)
+        .filter(|(_, _, speaker)| {
+            !characters
+                .iter()
+                .any(|character| character.id == speaker.character)
+        })
+        .map(|(scene, index, speaker)| format!("{scene} line {index}: {speaker}"))
+        .collect()
+}
+
+/// Lines in `scenes` asking for a portrait their speaker doesn't have, saying where they are.
+pub fn missing_portraits(scenes: &[DatingScene], characters: &[Character]) -> Vec<String> {
+    speakers(scenes)
+        .filter(|(_, _, speaker)| {
+            let expression = speaker.expression.as_deref().unwrap_or(NEUTRAL);
+            characters
+                .iter()
+                .find(|character| character.id == speaker.character)
+                .is_some_and(|character| !character.portraits.contains_key(expression))
+        })
+        .map(|(scene, index, speaker)| format!("{scene} line {index}: {speaker}"))
+        .collect()
+}
+
+fn speakers(scenes: &[DatingScene]) -> impl Iterator<Item = (&SceneID, usize, &Speaker)> {
+    scenes.iter().flat_map(|scene| {
+        scene
+            .text
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, (speaker, _))| Some((&scene.id, index, speaker.as_ref()?)))
+    })
+}
+
+/// Where each of `count` portraits goes across a screen `width` wide, and how big they are.
+pub fn crew_layout(count: usize, width: f32) -> (Vec<f32>, f32) {
+    // seven fit at the size the ship screen was drawn for
+    let spacing = (width / 7.5).min(width * 0.9 / count.max(1) as f32);
+    let middle = (count as f32 - 1.0) / 2.0;
+    let positions = (0..count)
+        .map(|index| (index as f32 - middle) * spacing)
+        .collect();
+    (positions, spacing / 1.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speakers_can_have_an_expression() {
+        let speakers: Vec<Speaker> = serde_json::from_str(r#"["Joe", "Cat:scared"]"#).unwrap();
+        assert_eq!(speakers[0], Speaker::from("Joe".to_string()));
+        assert_eq!(speakers[1].expression.as_deref(), Some("scared"));
+        assert_eq!(
+            serde_json::to_string(&speakers[1]).unwrap(),
+            r#""Cat:scared""#
+        );
+    }
+
+    #[test]
+    fn shipped_scenes_only_use_known_characters_and_portraits() {
+        let characters = crate::load::load_characters();
+        let scenes = crate::load::load_scenes(&characters);
+        assert_eq!(unknown_speakers(&scenes, &characters), Vec::<String>::new());
+        assert_eq!(
+            missing_portraits(&scenes, &characters),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn crew_is_centered_and_fits_on_screen() {
+        let (positions, size) = crew_layout(7, 1500.0);
+        assert_eq!(positions[3], 0.0);
+        assert_eq!(positions[0], -positions[6]);
+        assert!(size <= 1500.0 / 9.0);
+
+        let (positions, size) = crew_layout(12, 1500.0);
+        assert!(positions[11] + size / 2.0 < 750.0);
+        assert_eq!(positions[0], -positions[11]);
+    }
+}
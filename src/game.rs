@@ -14,26 +14,26 @@ use std::{collections::VecDeque, time::Duration};
 
 use rand::prelude::*;
 
+pub mod cavegen;
+pub mod climbing;
 pub mod floodfill;
-use crate::dating_sim;
+pub mod fog;
+pub mod minimap;
+pub mod pause;
+pub mod tiled;
+use crate::{
+    dating_sim,
+    input::Action,
+    locale::Locale,
+    random::Random,
+    settings::{Music, Settings},
+};
 
 use self::floodfill::Floodfill;
+use self::tiled::TiledLoader;
 
 use super::{despawn_screen, GameState};
 
-#[derive(Resource)]
-struct Random {
-    rng: StdRng,
-}
-
-impl Default for Random {
-    fn default() -> Self {
-        Random {
-            rng: StdRng::from_seed([0xDA; 32]),
-        }
-    }
-}
-
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum ExplorationState {
     #[default]
@@ -42,11 +42,17 @@ enum ExplorationState {
 }
 
 pub fn game_plugin(app: &mut App) {
-    app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+    app.add_plugins((
+        FrameTimeDiagnosticsPlugin::default(),
+        fog::fog_plugin,
+        minimap::minimap_plugin,
+        climbing::climbing_plugin,
+        pause::pause_plugin,
+    ));
 
     let obj = Objectives {
-        time_limit: Some(5),
-        load_time: 0.0,
+        oxygen: None,
+        refilled_on: 0,
         accepted_missions: vec!["Take a shit".to_string()],
         day: 1,
         map_flags: vec![],
@@ -55,9 +61,12 @@ pub fn game_plugin(app: &mut App) {
     app.add_plugins((RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),))
         // .add_plugins(RapierDebugRenderPlugin::default())
         .init_asset_loader::<MapLoader>()
+        .init_asset_loader::<TiledLoader>()
         .init_asset::<MapAsset>()
         .insert_resource(obj)
-        .insert_resource(Random::default())
+        .init_resource::<PlayerPhysics>()
+        .register_type::<PlayerPhysics>()
+        .insert_resource(MapSource::from_args())
         .init_state::<ExplorationState>()
         .add_systems(Startup, load_map)
         .add_systems(
@@ -65,6 +74,11 @@ pub fn game_plugin(app: &mut App) {
             check_map_ready.run_if(in_state(ExplorationState::Waiting)),
         )
         .add_systems(OnEnter(ExplorationState::Ready), spawn_map)
+        .add_systems(
+            Update,
+            regenerate_map
+                .run_if(in_state(ExplorationState::Ready).and(resource_changed::<Random>)),
+        )
         .add_systems(
             OnEnter(GameState::Explore),
             (
@@ -76,6 +90,7 @@ pub fn game_plugin(app: &mut App) {
                 spawn_player,
                 spawn_triggers,
                 spawn_ui,
+                start_trip,
             )
                 .run_if(in_state(ExplorationState::Ready)),
         )
@@ -86,36 +101,183 @@ pub fn game_plugin(app: &mut App) {
                 update_camera,
                 update_diagnostics,
                 on_pickup,
+                on_oxygen_trigger,
                 time_pressure,
                 execute_animations,
+                (take_damage, flash_hurt).chain(),
             )
-                .run_if(in_state(GameState::Explore)),
+                .run_if(in_state(pause::PauseState::Running)),
+        )
+        .add_systems(
+            Update,
+            toggle_diagnostics
+                .run_if(in_state(GameState::Explore).and(resource_changed::<Settings>)),
         )
         .add_systems(
             PostUpdate,
             (check_triggers, read_character_controller_collisions)
-                .run_if(in_state(GameState::Explore)),
+                .run_if(in_state(pause::PauseState::Running)),
         )
         .insert_resource(Events::<WorldTriggerEvent>::default())
+        .add_event::<PlayerDamaged>()
         .add_systems(
             OnExit(GameState::Explore),
-            (despawn_screen::<OnExploration>, hide_map),
+            (store_oxygen, despawn_screen::<OnExploration>, hide_map),
         );
 }
 
-// The float value is the player movement speed in 'pixels/second'.
+// The velocity is in 'pixels/second'.
 #[derive(Component, Default)]
 pub struct Player {
-    speed: f32,
     velocity: Vec2,
     grounded: bool,
-    last_pos: Vec2,
+    // seconds since we were last on the ground
+    air_time: f32,
+    // seconds left for a buffered jump to still happen
+    jump_buffer: f32,
+    // rising from a jump that can still be cut short
+    jumping: bool,
+    // left the ground by jumping or flying, no coyote time until we land
+    jumped: bool,
+    // holding on to a pole, gravity doesn't apply
+    climbing: bool,
+    // burning oxygen to fly
+    thrusting: bool,
+    // poles left to place
+    poles: usize,
+}
+
+/// Tuning for `player_movement`, in pixels and seconds.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct PlayerPhysics {
+    pub run_speed: f32,
+    pub ground_acceleration: f32,
+    /// How quickly the player can change direction mid-air.
+    pub air_acceleration: f32,
+    pub gravity: f32,
+    /// Fraction of the vertical velocity lost per second while airborne.
+    pub air_drag: f32,
+    pub max_fall_speed: f32,
+    pub jump_speed: f32,
+    /// Multiplier on the upward velocity when jump is released early.
+    pub jump_cut: f32,
+    /// How long after walking off a ledge a jump is still allowed.
+    pub coyote_time: f32,
+    /// How long a jump pressed in the air is remembered before landing.
+    pub jump_buffer_time: f32,
+    pub climb_speed: f32,
+    /// Seconds of oxygen used per second outside the ship.
+    pub oxygen_drain: f32,
+    /// Upward acceleration of the jetpack, has to beat `gravity` to lift off.
+    pub jetpack_thrust: f32,
+    /// The jetpack stops accelerating past this upward speed.
+    pub jetpack_max_rise: f32,
+    /// Extra seconds of oxygen burnt per second of thrust.
+    pub jetpack_oxygen_drain: f32,
+    /// Landing faster than this hurts.
+    pub safe_landing_speed: f32,
+    /// Health lost per pixel/second above `safe_landing_speed`.
+    pub fall_damage: f32,
+    /// Seconds of oxygen lost to a hard landing, 0 to disable.
+    pub fall_oxygen_penalty: f32,
+}
+
+impl Default for PlayerPhysics {
+    fn default() -> Self {
+        PlayerPhysics {
+            run_speed: 600.0,
+            ground_acceleration: 6000.0,
+            air_acceleration: 2500.0,
+            gravity: 2000.0,
+            air_drag: 1.2,
+            max_fall_speed: 3000.0,
+            jump_speed: 1000.0,
+            jump_cut: 0.5,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.1,
+            climb_speed: 400.0,
+            oxygen_drain: 1.0,
+            jetpack_thrust: 3500.0,
+            jetpack_max_rise: 700.0,
+            jetpack_oxygen_drain: 4.0,
+            safe_landing_speed: 1100.0,
+            fall_damage: 0.1,
+            fall_oxygen_penalty: 1.0,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Health {
+    current: f32,
+    max: f32,
+}
+
+impl Health {
+    fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+}
+
+/// Flashes the player red for a moment after getting hurt.
+#[derive(Component)]
+struct Hurt(Timer);
+
+// how long the player can hold their breath once the tank is empty
+const OXYGEN_GRACE: f32 = 5.0;
+const BASE_OXYGEN_CAPACITY: f32 = 120.0;
+// a working filtration system lets the suit be filled further
+const FILTRATION_OXYGEN_BONUS: f32 = 60.0;
+const CANISTER_OXYGEN: f32 = 30.0;
+
+/// Seconds of breathing left in the suit.
+#[derive(Component, Debug)]
+pub struct Oxygen {
+    current: f32,
+    capacity: f32,
+    // seconds of oxygen used per second, not counting the jetpack
+    drain_rate: f32,
+}
+
+impl Oxygen {
+    /// The suit as it leaves the ship, with whatever was left from earlier trips that day.
+    fn for_trip(
+        objective: &mut Objectives,
+        context: &dating_sim::DatingContext,
+        physics: &PlayerPhysics,
+    ) -> Oxygen {
+        let mut capacity = BASE_OXYGEN_CAPACITY;
+        if context.get_flag("FiltrationFixed") >= 1 {
+            capacity += FILTRATION_OXYGEN_BONUS;
+        }
+
+        // the ship's tanks refill the suit overnight
+        let day = context.get_flag("Day");
+        if objective.refilled_on != day {
+            objective.oxygen = None;
+            objective.refilled_on = day;
+        }
+
+        Oxygen {
+            current: objective.oxygen.unwrap_or(capacity).min(capacity),
+            capacity,
+            drain_rate: physics.oxygen_drain,
+        }
+    }
+}
+
+#[derive(Event, Debug)]
+struct PlayerDamaged {
+    amount: f32,
 }
 
 #[derive(Resource, Debug, Clone)]
 pub struct Objectives {
-    time_limit: Option<usize>,
-    load_time: f64,
+    // what was left in the suit after the last trip, none for a full tank
+    oxygen: Option<f32>,
+    // the day the ship last refilled the suit
+    refilled_on: isize,
     accepted_missions: Vec<String>,
     day: usize,
     map_flags: Vec<String>,
@@ -129,23 +291,58 @@ pub struct MapAsset {
     // 1000x1000
     pub tiles: Vec<[Tile; 1000]>,
     floodfill: Floodfill,
+    triggers: Vec<TriggerSpawn>,
 }
 
 #[derive(Component)]
 struct LacksCollider;
 
+/// Where the exploration map comes from.
+#[derive(Resource, Clone, Debug)]
+pub enum MapSource {
+    File(String),
+    Generated,
+}
+
+impl MapSource {
+    /// `--generate-map` for a cave, or `--map <path>` for a map in the assets folder,
+    /// like a Tiled export. Otherwise the painted map.
+    fn from_args() -> MapSource {
+        if std::env::args().any(|arg| arg == "--generate-map") {
+            MapSource::Generated
+        } else {
+            MapSource::File(crate::arg_value("--map").unwrap_or_else(|| "Map/map.png".to_string()))
+        }
+    }
+
+    /// Identifies the map for things that are remembered per map, like explored cells.
+    pub fn key(&self, seed: u64) -> String {
+        match self {
+            MapSource::File(path) => path.clone(),
+            MapSource::Generated => format!("generated-{seed}"),
+        }
+    }
+}
+
 #[derive(Resource)]
 struct ExplorationMap {
     handle: Handle<MapAsset>,
     sprites: TileSprites,
+    // what a generated map was made from
+    seed: u64,
 }
 
 impl ExplorationMap {
     fn get_sprite(&self, tile: Tile) -> Sprite {
         match tile {
             Tile::Rock => self.sprites.rock.clone(),
+            Tile::Ice => self.sprites.ice.clone(),
             Tile::Error => self.sprites.nothing.clone(),
             Tile::Wall => self.sprites.backdrop.clone(),
+            Tile::Coal => self.sprites.coal.clone(),
+            Tile::Iron => self.sprites.iron.clone(),
+            Tile::Sulfur => self.sprites.sulfur.clone(),
+            Tile::Oil => self.sprites.oil.clone(),
             _ => self.sprites.nothing.clone(),
         }
     }
@@ -153,6 +350,7 @@ impl ExplorationMap {
 
 struct TileSprites {
     rock: Sprite,
+    ice: Sprite,
     nothing: Sprite,
     backdrop: Sprite,
     sodium: Sprite,
@@ -183,9 +381,17 @@ impl MapAsset {
             tiles.push(next_row);
         }
 
+        MapAsset::from_tiles(tiles, vec![])
+    }
+
+    pub fn from_tiles(tiles: Vec<[Tile; 1000]>, triggers: Vec<TriggerSpawn>) -> MapAsset {
         let floodfill = floodfill::floodfill_all(&tiles);
 
-        MapAsset { tiles, floodfill }
+        MapAsset {
+            tiles,
+            floodfill,
+            triggers,
+        }
     }
 }
 
@@ -210,8 +416,10 @@ impl AssetLoader for MapLoader {
         Ok(MapAsset::from_image(&image))
     }
 
+    // registered for png so it wins over the image loader when a MapAsset is requested,
+    // otherwise the Tiled loader would be picked as the fallback
     fn extensions(&self) -> &[&str] {
-        &["custom"]
+        &["png"]
     }
 }
 
@@ -242,8 +450,24 @@ fn check_map_ready(
     }
 }
 
-fn load_map(mut commands: Commands, asset_server: ResMut<AssetServer>) {
-    let map: Handle<MapAsset> = asset_server.load("Map/map.png");
+fn load_map(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    source: Res<MapSource>,
+    mut maps: ResMut<Assets<MapAsset>>,
+    mut rng: ResMut<Random>,
+    mut ready_state: ResMut<NextState<ExplorationState>>,
+) {
+    let map: Handle<MapAsset> = match &*source {
+        MapSource::File(path) => asset_server.load(path),
+        MapSource::Generated => {
+            let rng = &mut *rng;
+            let (tiles, triggers) = cavegen::generate(&mut rng.world, &mut rng.events);
+            // nothing to wait for
+            ready_state.set(ExplorationState::Ready);
+            maps.add(MapAsset::from_tiles(tiles, triggers))
+        }
+    };
     let make_sprite = |image: &str, coord| Sprite {
         image: asset_server.load(image),
         custom_size: Some(Vec2::new(100.5, 100.5)),
@@ -263,8 +487,15 @@ fn load_map(mut commands: Commands, asset_server: ResMut<AssetServer>) {
         ..make_sprite("Map/tileset_deeper_and_deeper.png", ROCK)
     };
 
+    // there is no ice in the tileset, frosted rock stands in for it
+    let ice = Sprite {
+        color: Color::srgb(0.6, 0.85, 1.0),
+        ..make_sprite("Map/tileset_deeper_and_deeper.png", ROCK)
+    };
+
     let sprites = TileSprites {
         rock: make_sprite("Map/tileset_deeper_and_deeper.png", ROCK),
+        ice,
         backdrop,
         sodium: make_sprite("Map/tileset_deeper_and_deeper.png", Vec2::new(144.0, 0.0)),
         calcium: make_sprite(
@@ -293,13 +524,14 @@ fn load_map(mut commands: Commands, asset_server: ResMut<AssetServer>) {
     commands.insert_resource(ExplorationMap {
         handle: map,
         sprites,
+        seed: rng.seed,
     });
 }
 
 #[derive(Component)]
 struct TimerHud;
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum Tile {
     #[default]
     Error = 0,
@@ -314,6 +546,22 @@ pub enum Tile {
 }
 
 impl Tile {
+    pub fn from_name(name: &str) -> Option<Tile> {
+        let tile = match name {
+            "Error" => Tile::Error,
+            "Rock" => Tile::Rock,
+            "Ice" => Tile::Ice,
+            "Oil" => Tile::Oil,
+            "Iron" => Tile::Iron,
+            "Air" => Tile::Air,
+            "Wall" => Tile::Wall,
+            "Sulfur" => Tile::Sulfur,
+            "Coal" => Tile::Coal,
+            _ => return None,
+        };
+        Some(tile)
+    }
+
     fn is_solid(&self) -> bool {
         use Tile as T;
         match self {
@@ -378,9 +626,10 @@ fn spawn_background(
 }
 
 fn play_music(mut commands: Commands, mut rng: ResMut<Random>, server: Res<AssetServer>) {
-    let random_number = rng.rng.gen_range(0..5);
+    let random_number = rng.audio.gen_range(0..5);
     commands.spawn((
         AudioPlayer::new(server.load(pick_ost(random_number))),
+        Music,
         OnExploration,
     ));
 }
@@ -390,6 +639,9 @@ fn spawn_player(
     server: Res<AssetServer>,
     mut rapier_config: Query<&mut RapierConfiguration>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut objective: ResMut<Objectives>,
+    dating_context: Res<dating_sim::DatingContext>,
+    physics: Res<PlayerPhysics>,
 ) {
     log::info!("spawning player");
 
@@ -399,10 +651,10 @@ fn spawn_player(
 
     let sprite_size = 100.0;
     let texture = server.load("Sprite/Player_Walking_Sprite-Sheet.png");
-    // the sprite sheet has 7 sprites arranged in a row, and they are all 24px x 24px
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 4, 1, None, None);
+    // 16px sprites, four walking in the first row and two climbing in the second
+    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 4, 2, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let player_animation_config = AnimationConfig::new(0, 0, 3, 10);
+    let player_animation_config = AnimationConfig::walking();
 
     let player_sprite = Sprite {
         custom_size: Some(Vec2::new(sprite_size, sprite_size)),
@@ -423,11 +675,11 @@ fn spawn_player(
             Transform::from_xyz(6800.0, -7500.0, 0.0),
             Collider::ball(sprite_size / 2.4),
             Player {
-                grounded: false,
-                speed: 10.0,
-                velocity: Vec2::ZERO,
-                last_pos: Vec2::new(200.0, -7000.0),
+                poles: climbing::STARTING_POLES,
+                ..default()
             },
+            Health::new(100.0),
+            Oxygen::for_trip(&mut objective, &dating_context, &physics),
             player_animation_config,
             OnExploration,
         ))
@@ -449,16 +701,52 @@ fn spawn_player(
 #[derive(Component)]
 struct TileMarker;
 
+#[derive(Component)]
+struct MapCollider;
+
 fn spawn_map(
     mut commands: Commands,
     map: Res<ExplorationMap>,
     maps: Res<Assets<MapAsset>>,
     mut rng: ResMut<Random>,
 ) {
-    let MapAsset {
-        ref tiles,
-        floodfill: ref flood,
-    } = &maps.get(&map.handle).unwrap();
+    spawn_tiles(
+        &mut commands,
+        &map,
+        maps.get(&map.handle).unwrap(),
+        &mut rng.world,
+    );
+}
+
+/// Makes a new cave when a save with another seed is loaded.
+fn regenerate_map(
+    mut commands: Commands,
+    source: Res<MapSource>,
+    mut map: ResMut<ExplorationMap>,
+    mut maps: ResMut<Assets<MapAsset>>,
+    mut rng: ResMut<Random>,
+    tiles: Query<Entity, With<TileMarker>>,
+    colliders: Query<Entity, With<MapCollider>>,
+) {
+    if !matches!(*source, MapSource::Generated) || map.seed == rng.seed {
+        return;
+    }
+    log::info!("generating the cave for seed {}", rng.seed);
+
+    for entity in tiles.iter().chain(&colliders) {
+        commands.entity(entity).despawn();
+    }
+
+    let rng = &mut *rng;
+    let (tiles, triggers) = cavegen::generate(&mut rng.world, &mut rng.events);
+    let asset = MapAsset::from_tiles(tiles, triggers);
+    spawn_tiles(&mut commands, &map, &asset, &mut rng.world);
+    maps.insert(&map.handle, asset);
+    map.seed = rng.seed;
+}
+
+fn spawn_tiles(commands: &mut Commands, map: &ExplorationMap, asset: &MapAsset, rng: &mut StdRng) {
+    let tiles = &asset.tiles;
 
     let flood = floodfill::floodfill_all(tiles);
     for region in flood.regions {
@@ -469,7 +757,11 @@ fn spawn_map(
             .map(|(x, y)| Vec2::new(x as f32 * 100.0 as f32 - 50.0, y as f32 * -100.0 + 50.0))
             .collect::<Vec<Vec2>>();
 
-        commands.spawn((RigidBody::Fixed, Collider::polyline(vertices, None)));
+        commands.spawn((
+            RigidBody::Fixed,
+            Collider::polyline(vertices, None),
+            MapCollider,
+        ));
     }
 
     let mut sprites = Vec::with_capacity(1000 * 1000);
@@ -481,8 +773,8 @@ fn spawn_map(
             sprites.push((
                 transform,
                 Sprite {
-                    flip_x: rng.rng.gen::<bool>(),
-                    flip_y: rng.rng.gen::<bool>(),
+                    flip_x: rng.gen::<bool>(),
+                    flip_y: rng.gen::<bool>(),
                     ..map.get_sprite(tiles[y][x])
                 },
                 TileMarker,
@@ -506,10 +798,48 @@ fn hide_map(mut tiles: Query<&mut Visibility, With<TileMarker>>) {
     }
 }
 
-fn spawn_triggers(mut commands: Commands, server: Res<AssetServer>) {
+fn spawn_triggers(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
     //Place all triggers
     let generous_trigger = Some(Vec2::new(500.0, 500.0));
 
+    // maps that place their own triggers replace the hand placed ones below
+    let map_triggers = &maps.get(&map.handle).unwrap().triggers;
+    for spawn in map_triggers {
+        if spawn.trigger_type == TriggerType::Pole {
+            climbing::spawn_pole(&mut commands, &server, spawn.position, spawn.size.y);
+            continue;
+        }
+
+        let sprite = spawn.sprite.as_deref().unwrap_or(match spawn.trigger_type {
+            TriggerType::Ship => "Sprite/SpacShip_Sprite.png",
+            _ => "Sprite/SpaceBox_Sprite.png",
+        });
+
+        commands.spawn(WorldTrigger {
+            transform: Transform::from_translation(spawn.position.extend(0.0)),
+            sprite: Sprite {
+                image: server.load(sprite),
+                custom_size: Some(spawn.size),
+                ..default()
+            },
+            collider: Collider::cuboid(spawn.size.x / 2.0, spawn.size.y / 2.0),
+            trigger: TriggerComponent {
+                id: spawn.trigger_type,
+                delete_on_trigger: spawn.delete_on_trigger,
+                flags: spawn.flag.clone(),
+            },
+            ..default()
+        });
+    }
+    if !map_triggers.is_empty() {
+        return;
+    }
+
     let make_trigger = |x, y, sprite, flag: &str| WorldTrigger {
         transform: Transform::from_xyz(x, y, 0.0),
         sprite: Sprite {
@@ -591,6 +921,26 @@ fn spawn_triggers(mut commands: Commands, server: Res<AssetServer>) {
         )
     });
 
+    // spare oxygen, one of them at the bottom of the shaft below
+    for (x, y) in [(77, 102), (323, 139), (411, 191), (320, 228), (473, 352)] {
+        commands.spawn(WorldTrigger {
+            trigger: TriggerComponent {
+                id: TriggerType::OxygenCanister,
+                delete_on_trigger: true,
+                flags: None,
+            },
+            ..make_trigger(
+                x as f32 * 100.0,
+                y as f32 * -100.0,
+                "Sprite/SpaceBox_Sprite.png",
+                "",
+            )
+        });
+    }
+
+    // the shaft around (320, 214) is a dead end without it
+    climbing::spawn_pole(&mut commands, &server, Vec2::new(32000.0, -22100.0), 1500.0);
+
     //beacon TODO
     commands.spawn(WorldTrigger {
         transform: Transform::from_xyz(39.0 * 100.0, -14100.0, 0.0),
@@ -624,7 +974,21 @@ pub fn is_exposed_and_solid(tiles: &Vec<[Tile; 1000]>, x: usize, y: usize) -> bo
     }
 }
 
-fn create_spaceship(mut commands: Commands, server: ResMut<AssetServer>) {
+fn create_spaceship(
+    mut commands: Commands,
+    server: ResMut<AssetServer>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
+    // a map that places its own ship gets that one instead, from spawn_triggers
+    let map_triggers = &maps.get(&map.handle).unwrap().triggers;
+    if map_triggers
+        .iter()
+        .any(|spawn| spawn.trigger_type == TriggerType::Ship)
+    {
+        return;
+    }
+
     // 65, 75
 
     commands.spawn(WorldTrigger {
@@ -658,6 +1022,35 @@ enum TriggerType {
     None,
     ItemPickup,
     Ship,
+    // not a trigger, but placed on maps the same way
+    Pole,
+    OxygenCanister,
+    RefillStation,
+}
+
+impl TriggerType {
+    fn from_name(name: &str) -> Option<TriggerType> {
+        match name {
+            "None" => Some(TriggerType::None),
+            "ItemPickup" => Some(TriggerType::ItemPickup),
+            "Ship" => Some(TriggerType::Ship),
+            "Pole" => Some(TriggerType::Pole),
+            "OxygenCanister" => Some(TriggerType::OxygenCanister),
+            "RefillStation" => Some(TriggerType::RefillStation),
+            _ => None,
+        }
+    }
+}
+
+/// A trigger that comes from the map file instead of being placed in `spawn_triggers`.
+#[derive(Clone, Debug)]
+pub struct TriggerSpawn {
+    position: Vec2,
+    size: Vec2,
+    trigger_type: TriggerType,
+    flag: Option<String>,
+    sprite: Option<String>,
+    delete_on_trigger: bool,
 }
 
 #[derive(Component, Default)]
@@ -753,27 +1146,62 @@ fn check_triggers(
 
 fn player_movement(
     time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut jump_buffer: Local<usize>,
+    actions: Res<ButtonInput<Action>>,
+    physics: Res<PlayerPhysics>,
     mut player_info: Query<(
         &mut Player,
+        &Transform,
         &mut Sprite,
         &mut AnimationConfig,
         &mut KinematicCharacterController,
     )>,
+    poles: Query<(&Transform, &climbing::Climbable)>,
+    dating_context: Res<dating_sim::DatingContext>,
 ) {
-    *jump_buffer = jump_buffer.saturating_sub(1);
-
-    for (mut player, mut sprite, mut animation, mut controller) in &mut player_info {
-        let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
-        let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
-        let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-        let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
-        if keyboard_input.just_pressed(KeyCode::KeyW) && !player.grounded {
-            *jump_buffer = 6;
+    let dt = time.delta_secs();
+    let has_jetpack = dating_context.get_flag("JetpackUnlocked") >= 1;
+    // up doubles as jump, except on a pole where it climbs
+    let jump_actions = [Action::Jump, Action::Up];
+
+    for (mut player, transform, mut sprite, mut animation, mut controller) in &mut player_info {
+        let left = actions.pressed(Action::Left);
+        let right = actions.pressed(Action::Right);
+        let up = actions.pressed(Action::Up);
+        let down = actions.pressed(Action::Down);
+        let thrust = has_jetpack && actions.pressed(Action::Jetpack);
+
+        // grab a pole by pushing up or down in front of it, let go by leaving it
+        let pole = climbing::pole_at(poles.iter(), transform.translation.truncate());
+        // lines us up with the pole we just grabbed
+        let mut snap = 0.0;
+        if let Some(pole_x) = pole {
+            if !player.climbing && (up || (down && !player.grounded)) {
+                player.climbing = true;
+                player.jumping = false;
+                player.jump_buffer = 0.0;
+                player.velocity.x = 0.0;
+                snap = pole_x - transform.translation.x;
+            }
+        } else {
+            player.climbing = false;
         }
 
-        animation.running = player.grounded && (left || right);
+        let frames = if player.climbing {
+            AnimationConfig::climbing()
+        } else {
+            AnimationConfig::walking()
+        };
+        if animation.first_sprite_index != frames.first_sprite_index {
+            *animation = frames;
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = animation.idle_index;
+            }
+        }
+        animation.running = if player.climbing {
+            up != down
+        } else {
+            player.grounded && (left || right)
+        };
 
         if left && !right {
             sprite.flip_x = true;
@@ -781,32 +1209,86 @@ fn player_movement(
             sprite.flip_x = false;
         }
 
-        // jump
-        if (keyboard_input.just_pressed(KeyCode::KeyW) || *jump_buffer > 0) && player.grounded {
-            player.velocity += Vec2::new(0.0, 100.0);
+        // walking, with less grip in the air
+        let x_axis = -(left as i8) + right as i8;
+        let target_speed = x_axis as f32 * physics.run_speed;
+        let acceleration = if player.grounded || player.climbing {
+            physics.ground_acceleration
+        } else {
+            physics.air_acceleration
+        };
+        let max_change = acceleration * dt;
+        player.velocity.x += (target_speed - player.velocity.x).clamp(-max_change, max_change);
+
+        player.thrusting = thrust && !player.climbing;
+
+        if player.climbing {
+            player.air_time = 0.0;
+            player.velocity.y = (up as i8 - down as i8) as f32 * physics.climb_speed;
+
+            // jumping off the pole, up is taken by climbing
+            if actions.just_pressed(Action::Jump) {
+                player.climbing = false;
+                player.jumping = true;
+                player.jumped = true;
+                player.velocity.y = physics.jump_speed;
+            }
+
+            controller.translation = Some(player.velocity * dt + Vec2::new(snap, 0.0));
+            continue;
+        }
+
+        // jump, remembering presses slightly before landing and allowing them slightly after leaving the ground
+        if player.grounded {
+            player.air_time = 0.0;
+        } else {
+            player.air_time += dt;
+        }
+        if actions.any_just_pressed(jump_actions) {
+            player.jump_buffer = physics.jump_buffer_time;
+        } else {
+            player.jump_buffer = (player.jump_buffer - dt).max(0.0);
+        }
+
+        let can_jump = player.grounded || (player.air_time < physics.coyote_time && !player.jumped);
+        if player.jump_buffer > 0.0 && can_jump {
+            player.velocity.y = physics.jump_speed;
             player.grounded = false;
-            *jump_buffer = 0;
+            player.jumping = true;
+            player.jumped = true;
+            player.jump_buffer = 0.0;
         }
 
-        let x_axis = -(left as i8) + right as i8;
+        // letting go early makes for a shorter jump
+        if player.jumping
+            && !player.thrusting
+            && !actions.any_pressed(jump_actions)
+            && player.velocity.y > 0.0
+        {
+            player.velocity.y *= physics.jump_cut;
+            player.jumping = false;
+        }
 
-        let mut move_delta = Vec2::new(x_axis as f32, 0.0);
-        if move_delta != Vec2::ZERO {
-            move_delta /= move_delta.length();
+        // the jetpack takes over from a jump, letting go of jump shouldn't cut it short
+        if player.thrusting {
+            if player.velocity.y < physics.jetpack_max_rise {
+                player.velocity.y =
+                    (player.velocity.y + physics.jetpack_thrust * dt).min(physics.jetpack_max_rise);
+            }
+            player.grounded = false;
+            player.jumping = false;
+            player.jumped = true;
         }
 
         if !player.grounded {
-            player.velocity += Vec2::new(0.0, -200.0) * time.delta_secs();
-            let drag = player.velocity * 0.02;
-            player.velocity -= drag;
+            player.velocity.y -= physics.gravity * dt;
+            player.velocity.y *= (-physics.air_drag * dt).exp();
         }
-        player.velocity = player.velocity.clamp_length_max(300.);
-
-        move_delta += player.velocity * time.delta_secs();
+        player.velocity.y = player.velocity.y.max(-physics.max_fall_speed);
 
         // Update the velocity on the rigid_body_component,
         // the bevy_rapier plugin will update the Sprite transform.
-        controller.translation = Some(move_delta * player.speed);
+        controller.translation = Some(player.velocity * dt);
     }
 }
 
@@ -842,9 +1324,11 @@ fn update_camera(
 fn read_character_controller_collisions(
     mut character_controller_outputs: Query<&mut KinematicCharacterControllerOutput>,
     bodies: Query<&RigidBody>,
-    mut player: Query<(&mut Player, &mut Transform), With<Player>>,
+    mut player: Query<&mut Player>,
+    physics: Res<PlayerPhysics>,
+    mut damage: EventWriter<PlayerDamaged>,
 ) {
-    let Ok((mut player, mut transform)) = player.get_single_mut() else {
+    let Ok(mut player) = player.get_single_mut() else {
         return;
     };
 
@@ -852,28 +1336,96 @@ fn read_character_controller_collisions(
         return;
     };
 
-    // we left the ground
-    if player.grounded && !output.grounded {
-        player.last_pos = transform.translation.xy();
-    }
-
     // we hit the ground
     if !player.grounded && output.grounded {
-        if player.velocity.y <= -165.0 {
-            transform.translation = player.last_pos.extend(0.0);
+        let impact = -player.velocity.y - physics.safe_landing_speed;
+        if impact > 0.0 {
+            damage.send(PlayerDamaged {
+                amount: impact * physics.fall_damage,
+            });
         }
         player.velocity.y = player.velocity.y.max(-0.1);
+        player.jumping = false;
+        player.jumped = false;
+    }
+
+    // bumping into walls and ceilings stops us
+    let (desired, effective) = (output.desired_translation, output.effective_translation);
+    if desired.y > 0.0 && effective.y < desired.y * 0.5 {
+        player.velocity.y = 0.0;
+    }
+    if desired.x != 0.0 && effective.x.abs() < desired.x.abs() * 0.5 {
+        player.velocity.x = 0.0;
     }
 
     player.grounded = output.grounded;
 }
 
-fn spawn_ui(
-    time: Res<Time>,
+fn take_damage(
     mut commands: Commands,
-    asset_server: ResMut<AssetServer>,
-    mut objective: ResMut<Objectives>,
+    mut reader: EventReader<PlayerDamaged>,
+    mut player: Query<(Entity, &mut Health, &mut Oxygen), With<Player>>,
+    physics: Res<PlayerPhysics>,
+    mut dating_context: ResMut<dating_sim::DatingContext>,
+    mut menu_state: ResMut<NextState<GameState>>,
+    server: Res<AssetServer>,
 ) {
+    let Ok((entity, mut health, mut oxygen)) = player.get_single_mut() else {
+        return;
+    };
+
+    for event in reader.read() {
+        health.current = (health.current - event.amount).max(0.0);
+        // a knock to the suit lets some air out
+        oxygen.current -= physics.fall_oxygen_penalty;
+
+        commands
+            .entity(entity)
+            .insert(Hurt(Timer::from_seconds(0.6, TimerMode::Once)));
+        commands.spawn((
+            AudioPlayer::new(server.load("sounds/RandomExtra.ogg")),
+            PlaybackSettings::DESPAWN,
+        ));
+
+        if health.current <= 0.0 {
+            log::info!("you have died");
+            // the evening dialogue can tell the trip went wrong
+            *dating_context
+                .flags
+                .entry("FailedTrips".to_owned())
+                .or_insert(0) += 1;
+            dating_context.flags.insert("FailedTrip".to_owned(), 1);
+            dating_context.flags.insert("Evening".to_owned(), 1);
+            menu_state.set(GameState::DatingSim);
+            break;
+        }
+    }
+}
+
+fn start_trip(mut dating_context: ResMut<dating_sim::DatingContext>) {
+    dating_context.flags.insert("FailedTrip".to_owned(), 0);
+}
+
+fn flash_hurt(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hurt: Query<(Entity, &mut Hurt, &mut Sprite)>,
+) {
+    for (entity, mut hurt, mut sprite) in &mut hurt {
+        hurt.0.tick(time.delta());
+
+        if hurt.0.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Hurt>();
+        } else if (hurt.0.elapsed_secs() * 10.0) as u32 % 2 == 0 {
+            sprite.color = Color::srgb(1.0, 0.2, 0.2);
+        } else {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
+
+fn spawn_ui(mut commands: Commands, asset_server: ResMut<AssetServer>) {
     let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
 
     let root_uinode = commands
@@ -926,14 +1478,35 @@ fn spawn_ui(
                         },
                         TextColor(WHITE.into()),
                     ));
+                    p.spawn((
+                        TextSpan::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 40.0,
+                            ..default()
+                        },
+                        TextColor(RED.into()),
+                    ));
+                    p.spawn((
+                        TextSpan::default(),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 40.0,
+                            ..default()
+                        },
+                        TextColor(WHITE.into()),
+                    ));
                 });
         })
         .id();
     commands.entity(root_uinode).add_children(&[left_column]);
-    objective.load_time = time.elapsed().as_secs_f64();
 }
 
-fn spawn_diagnostics_ui(mut commands: Commands, asset_server: ResMut<AssetServer>) {
+fn spawn_diagnostics_ui(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    settings: Res<Settings>,
+) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
 
     let root_uinode = commands
@@ -944,6 +1517,8 @@ fn spawn_diagnostics_ui(mut commands: Commands, asset_server: ResMut<AssetServer
                 justify_content: JustifyContent::SpaceBetween,
                 ..default()
             },
+            diagnostics_visibility(&settings),
+            DiagnosticsUi,
             OnExploration,
         ))
         .id();
@@ -997,32 +1572,118 @@ fn spawn_diagnostics_ui(mut commands: Commands, asset_server: ResMut<AssetServer
 fn time_pressure(
     time: Res<Time>,
     query: Query<Entity, With<TimerHud>>,
-    objective: ResMut<Objectives>,
+    physics: Res<PlayerPhysics>,
     mut writer: TextUiWriter,
     mut menu_state: ResMut<NextState<GameState>>,
     mut dating_context: ResMut<dating_sim::DatingContext>,
+    mut player: Query<(&Health, &Player, &mut Oxygen)>,
+    locale: Res<Locale>,
 ) {
-    let mut t = time.elapsed().as_secs_f64() - objective.load_time;
     let entity = query.single();
+    let Ok((health, player, mut oxygen)) = player.get_single_mut() else {
+        return;
+    };
 
-    if let Some(timer) = objective.time_limit {
-        t = (timer as f64) - t;
+    *writer.text(entity, 3) = format!(
+        "\n{}",
+        locale.format(
+            "hud-health",
+            &[
+                ("current", &health.current.round()),
+                ("max", &health.max.round())
+            ]
+        )
+    );
+    *writer.text(entity, 4) = format!(
+        "\n{}",
+        locale.format("hud-poles", &[("poles", &player.poles)])
+    );
+
+    let mut burn_rate = oxygen.drain_rate;
+    if player.thrusting {
+        burn_rate += physics.jetpack_oxygen_drain;
+    }
+    oxygen.current -= burn_rate * time.delta_secs();
+
+    *writer.text(entity, 0) = format!(
+        "{}\n",
+        locale.format(
+            "hud-oxygen",
+            &[
+                ("current", &oxygen.current.max(0.0).round()),
+                ("capacity", &oxygen.capacity.round()),
+                ("rate", &burn_rate.round()),
+            ]
+        )
+    );
+
+    if oxygen.current < -OXYGEN_GRACE {
+        log::info!("you have run out of oxygen");
+        menu_state.set(GameState::DatingSim);
+        dating_context.flags.insert("Evening".to_owned(), 1);
+    } else if oxygen.current < 0.0 {
+        *writer.text(entity, 1) = locale.text("hud-return").to_string();
+    } else {
+        writer.text(entity, 1).clear();
+    }
+}
 
-        if t < -5.0 {
-            println!("You have run out of oxygen");
-            menu_state.set(GameState::DatingSim);
-            dating_context.flags.insert("Evening".to_owned(), 1);
-        } else if t < 0.0 {
-            *writer.text(entity, 1) = format!("You must return now!",);
-        } else {
-            *writer.text(entity, 0) = format!("Oxygen time: {t:.0}\n",);
+fn on_oxygen_trigger(
+    mut reader: EventReader<WorldTriggerEvent>,
+    mut oxygen: Query<&mut Oxygen>,
+    mut commands: Commands,
+    server: Res<AssetServer>,
+) {
+    let Ok(mut oxygen) = oxygen.get_single_mut() else {
+        return;
+    };
+
+    for event in reader.read() {
+        match event.trigger_type {
+            TriggerType::Ship | TriggerType::RefillStation => {
+                oxygen.current = oxygen.capacity;
+            }
+            TriggerType::OxygenCanister => {
+                commands.spawn((
+                    AudioPlayer::new(server.load("sounds/Pickup.ogg")),
+                    PlaybackSettings::DESPAWN,
+                ));
+                oxygen.current = (oxygen.current.max(0.0) + CANISTER_OXYGEN).min(oxygen.capacity);
+            }
+            _ => {}
         }
     }
 }
 
+fn store_oxygen(oxygen: Query<&Oxygen>, mut objective: ResMut<Objectives>) {
+    if let Ok(oxygen) = oxygen.get_single() {
+        objective.oxygen = Some(oxygen.current.max(0.0));
+    }
+}
+
 #[derive(Component)]
 struct TextChanges;
 
+#[derive(Component)]
+struct DiagnosticsUi;
+
+fn diagnostics_visibility(settings: &Settings) -> Visibility {
+    if settings.show_diagnostics {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn toggle_diagnostics(
+    settings: Res<Settings>,
+    mut diagnostics: Query<&mut Visibility, With<DiagnosticsUi>>,
+) {
+    for mut visibility in &mut diagnostics {
+        *visibility = diagnostics_visibility(&settings);
+    }
+}
+
 fn update_diagnostics(
     mut fps_history: Local<VecDeque<f64>>,
     mut time_history: Local<VecDeque<Duration>>,
@@ -1130,6 +1791,16 @@ impl AnimationConfig {
         }
     }
 
+    /// The player's walk cycle.
+    fn walking() -> Self {
+        Self::new(0, 0, 3, 10)
+    }
+
+    /// The player's row for going up and down a pole.
+    fn climbing() -> Self {
+        Self::new(4, 4, 5, 6)
+    }
+
     fn timer_from_fps(fps: u8) -> Timer {
         Timer::new(
             Duration::from_secs_f32(1.0 / (fps as f32)),
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use super::{Tile, TriggerSpawn, TriggerType};
+
+// everything above this row is open sky, the ship lands right on top of it
+const SURFACE: usize = 77;
+// the ship and the player both start around here
+const SHIP: (usize, usize) = (60, SURFACE - 2);
+
+const FILL_CHANCE: f64 = 0.5;
+const SMOOTHING_PASSES: usize = 5;
+
+// (ore, shallowest row, deepest row, number of veins)
+const VEINS: [(Tile, usize, usize, usize); 5] = [
+    (Tile::Coal, SURFACE + 5, 350, 120),
+    (Tile::Ice, SURFACE + 5, 500, 60),
+    (Tile::Iron, 250, 650, 100),
+    (Tile::Sulfur, 450, 850, 80),
+    (Tile::Oil, 650, 990, 60),
+];
+
+// (flag, shallowest row, deepest row)
+const PICKUPS: [(&str, usize, usize); 8] = [
+    ("CoalCollected", SURFACE + 10, 300),
+    ("GreenhouseFixed", SURFACE + 10, 300),
+    ("PotassiumCollected", 200, 450),
+    ("IronCollected", 250, 600),
+    ("CalciumCollected", 350, 700),
+    ("SulfurCollected", 450, 800),
+    ("SodiumCollected", 550, 900),
+    ("OilCollected", 650, 990),
+];
+
+// spare oxygen scattered around, more of it the deeper you go
+const CANISTERS: [(usize, usize, usize); 3] =
+    [(SURFACE + 10, 350, 4), (350, 700, 6), (700, 990, 8)];
+
+/// Generates a cave map with cellular automata.
+///
+/// The same rng states always produce the same map, with the rock shaped by `world`
+/// and the pickups placed by `events`. There is always a walkable tunnel from the
+/// ship down to the bottom of the map, and every pickup is placed somewhere that
+/// can be reached from the ship.
+pub fn generate(
+    world: &mut impl Rng,
+    events: &mut impl Rng,
+) -> (Vec<[Tile; 1000]>, Vec<TriggerSpawn>) {
+    let mut tiles = vec![[Tile::Rock; 1000]; 1000];
+
+    for (y, row) in tiles.iter_mut().enumerate() {
+        for (x, tile) in row.iter_mut().enumerate() {
+            *tile = if is_border(x, y) {
+                Tile::Rock
+            } else if y < SURFACE {
+                Tile::Air
+            } else if world.gen_bool(FILL_CHANCE) {
+                Tile::Rock
+            } else {
+                Tile::Air
+            };
+        }
+    }
+
+    for _ in 0..SMOOTHING_PASSES {
+        tiles = smooth(&tiles);
+    }
+
+    // keep a solid crust for the ship to land on
+    for row in &mut tiles[SURFACE..SURFACE + 3] {
+        row[1..999].fill(Tile::Rock);
+    }
+
+    carve_tunnel(&mut tiles, world);
+
+    for (ore, top, bottom, count) in VEINS {
+        for _ in 0..count {
+            place_vein(&mut tiles, world, ore, top, bottom);
+        }
+    }
+
+    let reachable = reachable_from(&tiles, SHIP);
+    let mut triggers = PICKUPS
+        .iter()
+        .filter_map(|&(flag, top, bottom)| {
+            let position = find_floor(&tiles, &reachable, events, top, bottom)?;
+            Some(TriggerSpawn {
+                flag: Some(flag.to_string()),
+                ..pickup(position, TriggerType::ItemPickup)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (top, bottom, count) in CANISTERS {
+        for _ in 0..count {
+            if let Some(position) = find_floor(&tiles, &reachable, events, top, bottom) {
+                triggers.push(pickup(position, TriggerType::OxygenCanister));
+            }
+        }
+    }
+
+    (tiles, triggers)
+}
+
+fn is_border(x: usize, y: usize) -> bool {
+    matches!((x, y), (0 | 999, _) | (_, 0 | 999))
+}
+
+fn smooth(tiles: &[[Tile; 1000]]) -> Vec<[Tile; 1000]> {
+    let mut next = tiles.to_vec();
+
+    for y in SURFACE..1000 {
+        for x in 0..1000 {
+            if is_border(x, y) {
+                continue;
+            }
+
+            let mut solid_neighbors = 0;
+            for ny in y - 1..=y + 1 {
+                for nx in x - 1..=x + 1 {
+                    if (nx, ny) != (x, y) && tiles[ny][nx].is_solid() {
+                        solid_neighbors += 1;
+                    }
+                }
+            }
+
+            next[y][x] = match solid_neighbors {
+                5.. => Tile::Rock,
+                4 => tiles[y][x],
+                _ => Tile::Air,
+            };
+        }
+    }
+
+    next
+}
+
+/// Digs a staircase from the ship to the bottom of the map.
+///
+/// The tunnel never drops more than one tile per column, so it can be walked and
+/// jumped back up.
+fn carve_tunnel(tiles: &mut [[Tile; 1000]], rng: &mut impl Rng) {
+    const RADIUS: usize = 2;
+
+    let (mut x, mut y) = SHIP;
+    let mut dir: isize = 1;
+
+    while y < 990 - RADIUS {
+        for row in &mut tiles[y - RADIUS..=y + RADIUS] {
+            row[x - RADIUS..=x + RADIUS].fill(Tile::Air);
+        }
+
+        if rng.gen_bool(0.02) || !(10..990).contains(&(x as isize + dir)) {
+            dir = -dir;
+        }
+        x = (x as isize + dir) as usize;
+        if rng.gen_bool(0.5) {
+            y += 1;
+        }
+    }
+}
+
+fn place_vein(
+    tiles: &mut [[Tile; 1000]],
+    rng: &mut impl Rng,
+    ore: Tile,
+    top: usize,
+    bottom: usize,
+) {
+    let mut x = rng.gen_range(1..999);
+    let mut y = rng.gen_range(top..bottom);
+
+    for _ in 0..rng.gen_range(8..30) {
+        if tiles[y][x] == Tile::Rock {
+            tiles[y][x] = ore;
+        }
+        x = (x as isize + rng.gen_range(-1..=1)).clamp(1, 998) as usize;
+        y = (y as isize + rng.gen_range(-1..=1)).clamp(top as isize, bottom as isize) as usize;
+    }
+}
+
+fn reachable_from(tiles: &[[Tile; 1000]], start: (usize, usize)) -> Vec<[bool; 1000]> {
+    let mut reachable = vec![[false; 1000]; 1000];
+    let mut queue = VecDeque::from([start]);
+    reachable[start.1][start.0] = true;
+
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if !reachable[ny][nx] && !tiles[ny][nx].is_solid() {
+                reachable[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    reachable
+}
+
+fn find_floor(
+    tiles: &[[Tile; 1000]],
+    reachable: &[[bool; 1000]],
+    rng: &mut impl Rng,
+    top: usize,
+    bottom: usize,
+) -> Option<Vec2> {
+    // somewhere the player can stand, with a bit of headroom
+    let candidates = (top..bottom)
+        .flat_map(|y| (1..999).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            reachable[y][x] && !tiles[y - 1][x].is_solid() && tiles[y + 1][x].is_solid()
+        })
+        .collect::<Vec<_>>();
+
+    let &(x, y) = candidates.choose(rng)?;
+
+    Some(Vec2::new(x as f32 * 100.0, y as f32 * -100.0))
+}
+
+fn pickup(position: Vec2, trigger_type: TriggerType) -> TriggerSpawn {
+    TriggerSpawn {
+        position,
+        size: Vec2::new(500.0, 500.0),
+        trigger_type,
+        flag: None,
+        sprite: None,
+        delete_on_trigger: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile_at(spawn: &TriggerSpawn) -> (usize, usize) {
+        (
+            (spawn.position.x / 100.0) as usize,
+            (spawn.position.y / -100.0) as usize,
+        )
+    }
+
+    fn generate_seeded(world: u8, events: u8) -> (Vec<[Tile; 1000]>, Vec<TriggerSpawn>) {
+        generate(
+            &mut StdRng::from_seed([world; 32]),
+            &mut StdRng::from_seed([events; 32]),
+        )
+    }
+
+    #[test]
+    fn deterministic() {
+        let (tiles_a, triggers_a) = generate_seeded(0xDA, 0xDA);
+        let (tiles_b, triggers_b) = generate_seeded(0xDA, 0xDA);
+
+        assert!(tiles_a == tiles_b);
+        assert_eq!(
+            triggers_a.iter().map(|t| t.position).collect::<Vec<_>>(),
+            triggers_b.iter().map(|t| t.position).collect::<Vec<_>>(),
+        );
+
+        let (tiles_c, _) = generate_seeded(0x12, 0xDA);
+        assert!(tiles_a != tiles_c);
+
+        // moving the pickups around leaves the rock alone
+        let (tiles_d, triggers_d) = generate_seeded(0xDA, 0x12);
+        assert!(tiles_a == tiles_d);
+        assert_ne!(triggers_a[0].position, triggers_d[0].position);
+    }
+
+    #[test]
+    fn pickups_reachable_from_ship() {
+        let (tiles, triggers) = generate_seeded(0xDA, 0xDA);
+        let reachable = reachable_from(&tiles, SHIP);
+
+        let pickups = triggers
+            .iter()
+            .filter(|t| t.trigger_type == TriggerType::ItemPickup)
+            .count();
+        assert_eq!(pickups, PICKUPS.len());
+        for trigger in &triggers {
+            let (x, y) = tile_at(trigger);
+            assert!(reachable[y][x], "{:?} at {x}, {y}", trigger.flag);
+        }
+
+        // the tunnel goes all the way down
+        assert!(reachable[980..].iter().any(|row| row.contains(&true)));
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+use crate::input::Action;
+
+use super::{pause::PauseState, ExplorationMap, MapAsset, OnExploration, Player, Tile};
+
+// how far a placed pole reaches up and down from the player, in tiles
+const MAX_POLE_REACH: usize = 20;
+pub const STARTING_POLES: usize = 3;
+
+/// A pole or ladder the player can climb while overlapping it.
+#[derive(Component)]
+pub struct Climbable {
+    half_size: Vec2,
+}
+
+pub fn climbing_plugin(app: &mut App) {
+    app.add_systems(Update, place_pole.run_if(in_state(PauseState::Running)));
+}
+
+/// Spawns a pole centered on `position`, `height` pixels tall.
+pub fn spawn_pole(commands: &mut Commands, server: &AssetServer, position: Vec2, height: f32) {
+    commands.spawn((
+        Sprite {
+            image: server.load("Sprite/Pole_sprite.png"),
+            custom_size: Some(Vec2::new(100.0, height)),
+            // one 32px sprite per tile, repeated along the pole
+            image_mode: SpriteImageMode::Tiled {
+                tile_x: false,
+                tile_y: true,
+                stretch_value: 100.0 / 32.0,
+            },
+            ..default()
+        },
+        Transform::from_translation(position.extend(-0.5)),
+        Climbable {
+            half_size: Vec2::new(50.0, height / 2.0),
+        },
+        OnExploration,
+    ));
+}
+
+/// The x coordinate of the pole at `position`, if there is one.
+pub fn pole_at<'a>(
+    mut poles: impl Iterator<Item = (&'a Transform, &'a Climbable)>,
+    position: Vec2,
+) -> Option<f32> {
+    poles
+        .find(|(transform, pole)| {
+            (transform.translation.truncate() - position)
+                .abs()
+                .cmple(pole.half_size)
+                .all()
+        })
+        .map(|(transform, _)| transform.translation.x)
+}
+
+/// Puts up a pole from the floor to the ceiling of the column the player is in.
+fn place_pole(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    server: Res<AssetServer>,
+    mut player: Query<(&mut Player, &Transform)>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
+    if !actions.just_pressed(Action::Interact) {
+        return;
+    }
+    let Ok((mut player, transform)) = player.get_single_mut() else {
+        return;
+    };
+    if player.poles == 0 {
+        log::info!("no poles left to place");
+        return;
+    }
+
+    let tiles = &maps.get(&map.handle).unwrap().tiles;
+    let x = (transform.translation.x / 100.0).round().clamp(0.0, 999.0) as usize;
+    let y = (transform.translation.y / -100.0).round().clamp(0.0, 999.0) as usize;
+
+    let (top, bottom) = pole_span(tiles, x, y);
+    let height = (bottom - top + 1) as f32 * 100.0;
+    let center = Vec2::new(x as f32 * 100.0, (top + bottom) as f32 * -50.0);
+
+    spawn_pole(&mut commands, &server, center, height);
+    player.poles -= 1;
+}
+
+/// The first and last open row of the column around `(x, y)`, limited to [`MAX_POLE_REACH`].
+fn pole_span(tiles: &[[Tile; 1000]], x: usize, y: usize) -> (usize, usize) {
+    let open = |y: usize| !tiles[y][x].is_solid();
+
+    let mut top = y;
+    while top > 0 && y - top < MAX_POLE_REACH && open(top - 1) {
+        top -= 1;
+    }
+    let mut bottom = y;
+    while bottom < 999 && bottom - y < MAX_POLE_REACH && open(bottom + 1) {
+        bottom += 1;
+    }
+
+    (top, bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pole_spans_floor_to_ceiling() {
+        let mut tiles = vec![[Tile::Rock; 1000]; 1000];
+        // a shaft at x = 5 from row 10 to row 40
+        for row in &mut tiles[10..=40] {
+            row[5] = Tile::Air;
+        }
+
+        assert_eq!(pole_span(&tiles, 5, 38), (18, 40));
+        assert_eq!(pole_span(&tiles, 5, 12), (10, 32));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    sprite::Anchor,
+};
+
+use super::{
+    is_exposed_and_solid, ExplorationMap, ExplorationState, MapAsset, MapSource, OnExploration,
+    Player, Tile,
+};
+use crate::{dating_sim, random::Random, GameState};
+
+// how far the player can see, in tiles
+const SIGHT_RADIUS: isize = 8;
+
+/// Which cells of each map the player has seen, keyed by [`MapSource::key`].
+#[derive(Resource, Default)]
+pub struct ExploredMaps {
+    maps: HashMap<String, Vec<bool>>,
+    current: String,
+}
+
+impl ExploredMaps {
+    pub fn current(&mut self) -> &mut Vec<bool> {
+        self.maps
+            .entry(self.current.clone())
+            .or_insert_with(|| vec![false; 1000 * 1000])
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        self.maps
+            .get(&self.current)
+            .is_some_and(|cells| cells[y * 1000 + x])
+    }
+
+    pub fn to_save(&self) -> HashMap<String, Vec<u32>> {
+        self.maps
+            .iter()
+            .map(|(key, cells)| (key.clone(), to_runs(cells)))
+            .collect()
+    }
+
+    pub fn restore(&mut self, saved: &HashMap<String, Vec<u32>>) {
+        self.maps = saved
+            .iter()
+            .map(|(key, runs)| (key.clone(), from_runs(runs, 1000 * 1000)))
+            .collect();
+    }
+}
+
+/// Black where the player hasn't been, one pixel per tile.
+#[derive(Resource)]
+pub struct Fog {
+    pub image: Handle<Image>,
+}
+
+#[derive(Component)]
+struct FogOverlay;
+
+pub fn fog_plugin(app: &mut App) {
+    app.init_resource::<ExploredMaps>()
+        .add_systems(Startup, init_fog)
+        .add_systems(Update, follow_seed.run_if(resource_changed::<Random>))
+        .add_systems(
+            OnEnter(GameState::Explore),
+            spawn_fog.run_if(in_state(ExplorationState::Ready)),
+        )
+        .add_systems(
+            Update,
+            reveal_around_player.run_if(in_state(GameState::Explore)),
+        );
+}
+
+fn init_fog(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut explored: ResMut<ExploredMaps>,
+    source: Res<MapSource>,
+    random: Res<Random>,
+) {
+    explored.current = source.key(random.seed);
+
+    let image = Image::new_fill(
+        Extent3d {
+            width: 1000,
+            height: 1000,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    commands.insert_resource(Fog {
+        image: images.add(image),
+    });
+}
+
+// a loaded save can bring its own seed, and with it its own generated cave
+fn follow_seed(mut explored: ResMut<ExploredMaps>, source: Res<MapSource>, random: Res<Random>) {
+    let key = source.key(random.seed);
+    if explored.current != key {
+        explored.current = key;
+    }
+}
+
+fn spawn_fog(
+    mut commands: Commands,
+    fog: Res<Fog>,
+    mut images: ResMut<Assets<Image>>,
+    mut explored: ResMut<ExploredMaps>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+    context: Res<dating_sim::DatingContext>,
+) {
+    let tiles = &maps.get(&map.handle).unwrap().tiles;
+    let cells = explored.current();
+
+    // a scan from the ship maps out the cave walls in advance
+    if context.get_flag("PerformedScan") >= 1 {
+        for y in 0..1000 {
+            for x in 0..1000 {
+                if is_exposed_and_solid(tiles, x, y) {
+                    cells[y * 1000 + x] = true;
+                }
+            }
+        }
+    }
+
+    // the save we came from might have a different idea of what has been explored
+    let image = images.get_mut(&fog.image).unwrap();
+    for (i, &seen) in cells.iter().enumerate() {
+        image.data[i * 4 + 3] = if seen { 0 } else { 255 };
+    }
+
+    commands.spawn((
+        Sprite {
+            image: fog.image.clone(),
+            custom_size: Some(Vec2::new(100_000.0, 100_000.0)),
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        // tile (0, 0) is centered on the origin, and the fog covers everything but the ui
+        Transform::from_xyz(-50.0, 50.0, 5.0),
+        FogOverlay,
+        OnExploration,
+    ));
+}
+
+fn reveal_around_player(
+    mut last_tile: Local<Option<(usize, usize)>>,
+    player: Query<&Transform, With<Player>>,
+    fog: Res<Fog>,
+    mut images: ResMut<Assets<Image>>,
+    mut explored: ResMut<ExploredMaps>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+
+    let x = (player.translation.x / 100.0).round().clamp(0.0, 999.0) as usize;
+    let y = (player.translation.y / -100.0).round().clamp(0.0, 999.0) as usize;
+
+    if *last_tile == Some((x, y)) {
+        return;
+    }
+    *last_tile = Some((x, y));
+
+    let tiles = &maps.get(&map.handle).unwrap().tiles;
+    let cells = explored.current();
+
+    let revealed = visible_cells(tiles, (x, y), SIGHT_RADIUS)
+        .into_iter()
+        .filter(|&(x, y)| !cells[y * 1000 + x])
+        .collect::<Vec<_>>();
+
+    if revealed.is_empty() {
+        return;
+    }
+
+    let image = images.get_mut(&fog.image).unwrap();
+    for (x, y) in revealed {
+        cells[y * 1000 + x] = true;
+        image.data[(y * 1000 + x) * 4 + 3] = 0;
+    }
+}
+
+/// Every cell within `radius` that has an unobstructed line to `origin`.
+///
+/// Walls are visible themselves, but hide whatever is behind them.
+fn visible_cells(
+    tiles: &[[Tile; 1000]],
+    origin: (usize, usize),
+    radius: isize,
+) -> Vec<(usize, usize)> {
+    let (ox, oy) = (origin.0 as isize, origin.1 as isize);
+    let mut visible = Vec::new();
+
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (tx, ty) = (ox + dx, oy + dy);
+            if dx * dx + dy * dy > radius * radius
+                || !(0..1000).contains(&tx)
+                || !(0..1000).contains(&ty)
+            {
+                continue;
+            }
+
+            // walk the line towards the target, giving up when something solid is in the way
+            let steps = dx.abs().max(dy.abs());
+            let blocked = (1..steps).any(|step| {
+                let x = ox + (dx * step + steps / 2 * dx.signum()) / steps;
+                let y = oy + (dy * step + steps / 2 * dy.signum()) / steps;
+                tiles[y as usize][x as usize].is_solid()
+            });
+
+            if !blocked {
+                visible.push((tx as usize, ty as usize));
+            }
+        }
+    }
+
+    visible
+}
+
+/// Run length encodes the cells, starting with a run of unexplored ones.
+fn to_runs(cells: &[bool]) -> Vec<u32> {
+    let mut runs = vec![];
+    let mut current = false;
+    let mut length = 0;
+
+    for &cell in cells {
+        if cell != current {
+            runs.push(length);
+            current = cell;
+            length = 0;
+        }
+        length += 1;
+    }
+    runs.push(length);
+
+    runs
+}
+
+fn from_runs(runs: &[u32], len: usize) -> Vec<bool> {
+    let mut cells = Vec::with_capacity(len);
+    let mut current = false;
+
+    // an edited save could ask for far more cells than the map has
+    for &run in runs {
+        let run = (run as usize).min(len - cells.len());
+        cells.extend(std::iter::repeat_n(current, run));
+        current = !current;
+    }
+    cells.resize(len, false);
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_roundtrip() {
+        let cells = vec![true, true, false, true, false, false, false];
+        let runs = to_runs(&cells);
+
+        assert_eq!(runs, vec![0, 2, 1, 1, 3]);
+        assert_eq!(from_runs(&runs, cells.len()), cells);
+
+        assert_eq!(
+            from_runs(&[1, u32::MAX, 5], 4),
+            vec![false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn walls_block_sight() {
+        let mut tiles = vec![[Tile::Air; 1000]; 1000];
+        // a wall two tiles to the right of the player
+        for row in &mut tiles[0..20] {
+            row[12] = Tile::Rock;
+        }
+
+        let visible = visible_cells(&tiles, (10, 10), 5);
+
+        assert!(visible.contains(&(11, 10)));
+        assert!(visible.contains(&(12, 10)));
+        assert!(!visible.contains(&(13, 10)));
+        assert!(visible.contains(&(7, 10)));
+        assert!(!visible.contains(&(10, 16)));
+    }
+}
@@ -0,0 +1,298 @@
+use bevy::{
+    color::palettes::css::*,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use super::{
+    fog::{ExploredMaps, Fog},
+    ExplorationMap, ExplorationState, MapAsset, OnExploration, Player, Tile, TriggerComponent,
+    TriggerType,
+};
+use crate::{input::Action, GameState};
+
+// how many tiles the corner minimap shows across
+const VIEW: f32 = 120.0;
+
+/// One pixel per tile of the exploration map.
+#[derive(Resource)]
+struct Minimap {
+    image: Handle<Image>,
+}
+
+#[derive(Component)]
+struct MinimapFrame {
+    full: bool,
+}
+
+#[derive(Component)]
+struct MinimapLayer;
+
+/// A dot on the map following a world entity.
+#[derive(Component)]
+struct MapMarker(Entity);
+
+pub fn minimap_plugin(app: &mut App) {
+    app.add_systems(OnEnter(ExplorationState::Ready), render_minimap)
+        .add_systems(
+            Update,
+            redraw_minimap.run_if(in_state(ExplorationState::Ready)),
+        )
+        .add_systems(
+            OnEnter(GameState::Explore),
+            spawn_minimap.run_if(in_state(ExplorationState::Ready)),
+        )
+        .add_systems(
+            Update,
+            (toggle_full_map, spawn_markers, update_minimap)
+                .chain()
+                .run_if(in_state(GameState::Explore)),
+        );
+}
+
+fn tile_color(tile: Tile) -> [u8; 4] {
+    match tile {
+        Tile::Air => [0x10, 0x10, 0x18, 0xFF],
+        Tile::Wall => [0x30, 0x30, 0x30, 0xFF],
+        Tile::Rock | Tile::Error => [0x8A, 0x7A, 0x66, 0xFF],
+        Tile::Ice => [0x55, 0xCC, 0xEE, 0xFF],
+        Tile::Oil => [0x20, 0x20, 0x80, 0xFF],
+        Tile::Iron => [0xB0, 0x50, 0x30, 0xFF],
+        Tile::Sulfur => [0xE0, 0xD0, 0x30, 0xFF],
+        Tile::Coal => [0x20, 0x20, 0x20, 0xFF],
+    }
+}
+
+fn render_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
+    let image = minimap_image(&maps.get(&map.handle).unwrap().tiles);
+    commands.insert_resource(Minimap {
+        image: images.add(image),
+    });
+}
+
+// a regenerated cave replaces the map in place
+fn redraw_minimap(
+    mut events: EventReader<AssetEvent<MapAsset>>,
+    mut images: ResMut<Assets<Image>>,
+    minimap: Res<Minimap>,
+    map: Res<ExplorationMap>,
+    maps: Res<Assets<MapAsset>>,
+) {
+    let modified = events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { id } if *id == map.handle.id()));
+    if modified {
+        let image = minimap_image(&maps.get(&map.handle).unwrap().tiles);
+        images.insert(&minimap.image, image);
+    }
+}
+
+fn minimap_image(tiles: &[[Tile; 1000]]) -> Image {
+    let data = tiles
+        .iter()
+        .flat_map(|row| row.iter().flat_map(|&tile| tile_color(tile)))
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: 1000,
+            height: 1000,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn spawn_minimap(mut commands: Commands, minimap: Res<Minimap>, fog: Res<Fog>) {
+    let layers = |builder: &mut ChildBuilder| {
+        for image in [minimap.image.clone(), fog.image.clone()] {
+            builder.spawn((
+                ImageNode::new(image),
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                MinimapLayer,
+            ));
+        }
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(15.),
+                bottom: Val::Px(15.),
+                width: Val::Px(240.),
+                height: Val::Px(240.),
+                border: UiRect::all(Val::Px(2.)),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BorderColor(WHITE.into()),
+            MinimapFrame { full: false },
+            OnExploration,
+        ))
+        .with_children(layers);
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            Visibility::Hidden,
+            OnExploration,
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        height: Val::Percent(90.),
+                        aspect_ratio: Some(1.0),
+                        border: UiRect::all(Val::Px(2.)),
+                        ..default()
+                    },
+                    BorderColor(WHITE.into()),
+                    MinimapFrame { full: true },
+                ))
+                .with_children(layers);
+        });
+}
+
+fn toggle_full_map(
+    actions: Res<ButtonInput<Action>>,
+    frames: Query<(&MinimapFrame, &Parent)>,
+    mut visibility: Query<&mut Visibility>,
+) {
+    if !actions.just_pressed(Action::Map) {
+        return;
+    }
+
+    for (frame, parent) in &frames {
+        if frame.full {
+            if let Ok(mut visibility) = visibility.get_mut(parent.get()) {
+                visibility.toggle_visible_hidden();
+            }
+        }
+    }
+}
+
+fn spawn_markers(
+    mut commands: Commands,
+    frames: Query<Entity, With<MinimapFrame>>,
+    new_triggers: Query<(Entity, &TriggerComponent), Added<TriggerComponent>>,
+    new_players: Query<Entity, Added<Player>>,
+) {
+    let new_markers = new_triggers
+        .iter()
+        .map(|(entity, trigger)| {
+            let color = match trigger.id {
+                TriggerType::Ship => LIME,
+                TriggerType::ItemPickup => YELLOW,
+                _ => AQUA,
+            };
+            (entity, color, 8.)
+        })
+        .chain(new_players.iter().map(|entity| (entity, WHITE, 10.)));
+
+    for (target, color, size) in new_markers {
+        for frame in &frames {
+            commands.entity(frame).with_children(|builder| {
+                builder.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(size),
+                        height: Val::Px(size),
+                        margin: UiRect::all(Val::Px(-size / 2.)),
+                        ..default()
+                    },
+                    BackgroundColor(color.into()),
+                    BorderRadius::MAX,
+                    Visibility::Hidden,
+                    MapMarker(target),
+                ));
+            });
+        }
+    }
+}
+
+fn update_minimap(
+    mut commands: Commands,
+    player: Query<&Transform, With<Player>>,
+    frames: Query<(&MinimapFrame, &Children)>,
+    mut layers: Query<&mut ImageNode, With<MinimapLayer>>,
+    mut markers: Query<(Entity, &MapMarker, &mut Node, &mut Visibility)>,
+    targets: Query<(&Transform, Option<&TriggerComponent>)>,
+    explored: Res<ExploredMaps>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let to_tile = |transform: &Transform| {
+        Vec2::new(
+            transform.translation.x / 100.0 + 0.5,
+            -transform.translation.y / 100.0 + 0.5,
+        )
+    };
+
+    let center = to_tile(player).clamp(Vec2::splat(VIEW / 2.0), Vec2::splat(1000.0 - VIEW / 2.0));
+    let corner_view = Rect::from_center_size(center, Vec2::splat(VIEW));
+    let full_view = Rect::new(0.0, 0.0, 1000.0, 1000.0);
+
+    for (frame, children) in &frames {
+        let view = if frame.full { full_view } else { corner_view };
+
+        for &child in children {
+            if let Ok(mut layer) = layers.get_mut(child) {
+                layer.rect = Some(view);
+            }
+
+            let Ok((marker, MapMarker(target), mut node, mut visibility)) = markers.get_mut(child)
+            else {
+                continue;
+            };
+            let Ok((transform, trigger)) = targets.get(*target) else {
+                // picked up or otherwise gone
+                commands.entity(marker).despawn_recursive();
+                continue;
+            };
+
+            let tile = to_tile(transform);
+            // the ship is always known, pickups only once their cell has been seen
+            let known = match trigger {
+                Some(trigger) if trigger.id != TriggerType::Ship => explored.is_explored(
+                    tile.x.clamp(0.0, 999.0) as usize,
+                    tile.y.clamp(0.0, 999.0) as usize,
+                ),
+                _ => true,
+            };
+
+            if known && view.contains(tile) {
+                let relative = (tile - view.min) / view.size();
+                node.left = Val::Percent(relative.x * 100.0);
+                node.top = Val::Percent(relative.y * 100.0);
+                *visibility = Visibility::Inherited;
+            } else {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::{Oxygen, Player};
+use crate::{
+    dating_sim, despawn_screen,
+    input::Action,
+    locale::Locale,
+    settings::SettingsMenu,
+    ui::{self, MenuActivated, MenuList},
+    GameState,
+};
+
+// seconds of oxygen it costs to be pulled back to the ship
+const RECALL_OXYGEN_PENALTY: f32 = 30.0;
+
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Explore)]
+pub(crate) enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Component)]
+struct OnPauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Settings,
+    ReturnToShip,
+    Quit,
+}
+
+pub fn pause_plugin(app: &mut App) {
+    app.add_sub_state::<PauseState>()
+        .add_systems(
+            Update,
+            toggle_pause.run_if(in_state(GameState::Explore).and(in_state(SettingsMenu::Closed))),
+        )
+        .add_systems(OnEnter(PauseState::Paused), (spawn_pause_menu, freeze))
+        .add_systems(
+            OnExit(PauseState::Paused),
+            (despawn_screen::<OnPauseMenu>, unfreeze),
+        )
+        .add_systems(
+            Update,
+            pause_menu_action.run_if(in_state(PauseState::Paused)),
+        );
+}
+
+fn toggle_pause(
+    actions: Res<ButtonInput<Action>>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    match state.get() {
+        PauseState::Running if actions.just_pressed(Action::Pause) => {
+            next_state.set(PauseState::Paused)
+        }
+        PauseState::Paused if actions.any_just_pressed([Action::Pause, Action::Cancel]) => {
+            next_state.set(PauseState::Running)
+        }
+        _ => {}
+    }
+}
+
+// oxygen, animations and everything else on virtual time stops with it
+fn freeze(mut time: ResMut<Time<Virtual>>, mut rapier_config: Query<&mut RapierConfiguration>) {
+    time.pause();
+    for mut config in &mut rapier_config {
+        config.physics_pipeline_active = false;
+    }
+}
+
+fn unfreeze(mut time: ResMut<Time<Virtual>>, mut rapier_config: Query<&mut RapierConfiguration>) {
+    time.unpause();
+    for mut config in &mut rapier_config {
+        config.physics_pipeline_active = true;
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            // above the minimap
+            GlobalZIndex(10),
+            OnPauseMenu,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new(locale.text("pause-title")),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 60.0,
+                    ..default()
+                },
+            ));
+
+            builder
+                .spawn((ui::menu_column(), MenuList::default()))
+                .with_children(|builder| {
+                    let items = [
+                        ("pause-resume", PauseAction::Resume),
+                        ("menu-settings", PauseAction::Settings),
+                        ("pause-return", PauseAction::ReturnToShip),
+                        ("menu-quit", PauseAction::Quit),
+                    ];
+                    for (index, (key, action)) in items.into_iter().enumerate() {
+                        ui::spawn_menu_item(builder, &font, index, locale.text(key), action);
+                    }
+                });
+        });
+}
+
+fn pause_menu_action(
+    mut reader: EventReader<MenuActivated>,
+    actions: Query<&PauseAction>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut settings_menu: ResMut<NextState<SettingsMenu>>,
+    mut menu_state: ResMut<NextState<GameState>>,
+    mut dating_context: ResMut<dating_sim::DatingContext>,
+    mut oxygen: Query<&mut Oxygen, With<Player>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for MenuActivated(entity) in reader.read() {
+        let Ok(action) = actions.get(*entity) else {
+            continue;
+        };
+
+        match action {
+            PauseAction::Resume => pause_state.set(PauseState::Running),
+            PauseAction::Settings => settings_menu.set(SettingsMenu::Open),
+            PauseAction::ReturnToShip => {
+                // the trip ends here, and calling for help isn't free
+                if let Ok(mut oxygen) = oxygen.get_single_mut() {
+                    oxygen.current -= RECALL_OXYGEN_PENALTY;
+                }
+                dating_context.flags.insert("Evening".to_owned(), 1);
+                menu_state.set(GameState::DatingSim);
+            }
+            PauseAction::Quit => {
+                exit.send(AppExit::Success);
+            }
+        }
+    }
+}
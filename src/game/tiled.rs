@@ -0,0 +1,346 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use super::{MapAsset, Tile, TriggerSpawn, TriggerType};
+
+// Tiled stores the flip flags in the top bits of every gid
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+#[derive(Deserialize)]
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    layers: Vec<Layer>,
+    #[serde(default)]
+    tilesets: Vec<Tileset>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Layer {
+    Tilelayer {
+        width: usize,
+        height: usize,
+        #[serde(default)]
+        data: Vec<u32>,
+        encoding: Option<String>,
+    },
+    Objectgroup {
+        objects: Vec<Object>,
+    },
+    Group {
+        layers: Vec<Layer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Tileset {
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TileDef>,
+}
+
+#[derive(Deserialize)]
+struct TileDef {
+    id: u32,
+    #[serde(default, rename = "type", alias = "class")]
+    class: String,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default, rename = "type", alias = "class")]
+    class: String,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+struct Property {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn property<'a>(properties: &'a [Property], name: &str) -> Option<&'a serde_json::Value> {
+    properties.iter().find(|p| p.name == name).map(|p| &p.value)
+}
+
+impl TiledMap {
+    fn tile_for_gid(&self, gid: u32) -> Tile {
+        let gid = gid & GID_MASK;
+        if gid == 0 {
+            return Tile::Air;
+        }
+
+        let Some(tileset) = self
+            .tilesets
+            .iter()
+            .filter(|set| set.firstgid <= gid)
+            .max_by_key(|set| set.firstgid)
+        else {
+            return Tile::Error;
+        };
+
+        let id = gid - tileset.firstgid;
+        let Some(def) = tileset.tiles.iter().find(|def| def.id == id) else {
+            return Tile::Error;
+        };
+
+        property(&def.properties, "tile")
+            .and_then(|value| value.as_str())
+            .or(Some(def.class.as_str()))
+            .and_then(Tile::from_name)
+            .unwrap_or(Tile::Error)
+    }
+
+    fn paint_layers(&self, layers: &[Layer], tiles: &mut [[Tile; 1000]]) -> anyhow::Result<()> {
+        for layer in layers {
+            match layer {
+                Layer::Tilelayer {
+                    width,
+                    height,
+                    data,
+                    encoding,
+                } => {
+                    if encoding.as_deref().is_some_and(|e| e != "csv") {
+                        anyhow::bail!(
+                            "tile layer encoding {encoding:?} is not supported, export as CSV"
+                        );
+                    }
+                    if *width > 1000 || *height > 1000 {
+                        anyhow::bail!(
+                            "tile layer is {width}x{height}, maps can be at most 1000x1000"
+                        );
+                    }
+                    if *width == 0 || data.len() != width * height {
+                        anyhow::bail!(
+                            "tile layer is {width}x{height} but has {} tiles",
+                            data.len()
+                        );
+                    }
+
+                    for (i, &gid) in data.iter().enumerate() {
+                        // empty cells let the layers below show through
+                        if gid & GID_MASK == 0 {
+                            continue;
+                        }
+                        tiles[i / width][i % width] = self.tile_for_gid(gid);
+                    }
+                }
+                Layer::Group { layers } => self.paint_layers(layers, tiles)?,
+                Layer::Objectgroup { .. } | Layer::Other => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_triggers(&self, layers: &[Layer], triggers: &mut Vec<TriggerSpawn>) {
+        for layer in layers {
+            match layer {
+                Layer::Objectgroup { objects } => {
+                    triggers.extend(objects.iter().filter_map(|obj| self.trigger_for(obj)))
+                }
+                Layer::Group { layers } => self.collect_triggers(layers, triggers),
+                Layer::Tilelayer { .. } | Layer::Other => {}
+            }
+        }
+    }
+
+    fn trigger_for(&self, obj: &Object) -> Option<TriggerSpawn> {
+        let name = property(&obj.properties, "trigger")
+            .and_then(|value| value.as_str())
+            .unwrap_or(&obj.class);
+
+        let Some(trigger_type) = TriggerType::from_name(name) else {
+            log::warn!("skipping map object with unknown trigger type {name:?}");
+            return None;
+        };
+
+        // point objects get the same generous size as the hand placed triggers
+        let (width, height) = if obj.width == 0.0 || obj.height == 0.0 {
+            (5.0 * self.tilewidth, 5.0 * self.tileheight)
+        } else {
+            (obj.width, obj.height)
+        };
+        let center_x = obj.x + width / 2.0;
+        let center_y = obj.y + height / 2.0;
+
+        // tile (x, y) is drawn centered on (x * 100, -y * 100)
+        let position = Vec2::new(
+            center_x / self.tilewidth * 100.0 - 50.0,
+            -(center_y / self.tileheight * 100.0 - 50.0),
+        );
+        let size = Vec2::new(
+            width / self.tilewidth * 100.0,
+            height / self.tileheight * 100.0,
+        );
+
+        Some(TriggerSpawn {
+            position,
+            size,
+            trigger_type,
+            flag: property(&obj.properties, "flag")
+                .and_then(|value| value.as_str())
+                .map(str::to_owned),
+            sprite: property(&obj.properties, "sprite")
+                .and_then(|value| value.as_str())
+                .map(str::to_owned),
+            delete_on_trigger: property(&obj.properties, "delete_on_trigger")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(trigger_type != TriggerType::Ship),
+        })
+    }
+
+    fn into_tiles(self) -> anyhow::Result<(Vec<[Tile; 1000]>, Vec<TriggerSpawn>)> {
+        if self.width > 1000 || self.height > 1000 {
+            anyhow::bail!(
+                "map is {}x{}, maps can be at most 1000x1000",
+                self.width,
+                self.height
+            );
+        }
+        if let Some(set) = self.tilesets.iter().find(|set| set.source.is_some()) {
+            anyhow::bail!(
+                "external tileset {:?} is not supported, embed it in the map",
+                set.source
+            );
+        }
+
+        // everything outside the map is solid rock, everything inside starts out empty
+        let mut tiles = vec![[Tile::Rock; 1000]; 1000];
+        for row in &mut tiles[..self.height] {
+            row[..self.width].fill(Tile::Air);
+        }
+
+        self.paint_layers(&self.layers, &mut tiles)?;
+
+        let mut triggers = Vec::new();
+        self.collect_triggers(&self.layers, &mut triggers);
+
+        Ok((tiles, triggers))
+    }
+}
+
+/// Loads maps exported from Tiled in the JSON (`.tmj`) format.
+///
+/// Tiles are mapped through a `tile` property (or the tile class) naming a [`Tile`],
+/// objects become [`TriggerSpawn`]s using their class or a `trigger` property together
+/// with the optional `flag`, `sprite` and `delete_on_trigger` properties.
+#[derive(Default)]
+pub struct TiledLoader;
+
+impl AssetLoader for TiledLoader {
+    type Asset = MapAsset;
+    type Settings = ();
+    type Error = anyhow::Error;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let map: TiledMap = serde_json::from_slice(&bytes)?;
+        let (tiles, triggers) = map.into_tiles()?;
+        Ok(MapAsset::from_tiles(tiles, triggers))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmj"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"{
+        "width": 3,
+        "height": 2,
+        "tilewidth": 16,
+        "tileheight": 16,
+        "tilesets": [{
+            "firstgid": 1,
+            "tiles": [
+                { "id": 0, "properties": [{ "name": "tile", "type": "string", "value": "Rock" }] },
+                { "id": 1, "type": "Coal" }
+            ]
+        }],
+        "layers": [
+            { "type": "tilelayer", "width": 3, "height": 2, "data": [1, 0, 2, 0, 0, 1] },
+            { "type": "objectgroup", "objects": [
+                { "x": 16, "y": 0, "width": 16, "height": 16, "type": "ItemPickup",
+                  "properties": [{ "name": "flag", "type": "string", "value": "CoalCollected" }] },
+                { "x": 0, "y": 0, "width": 32, "height": 32, "type": "Ship" },
+                { "x": 0, "y": 0, "type": "Spaceship" }
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn tile_layers() {
+        let map: TiledMap = serde_json::from_str(MAP).unwrap();
+        let (tiles, _) = map.into_tiles().unwrap();
+
+        assert!(matches!(tiles[0][0], Tile::Rock));
+        assert!(matches!(tiles[0][1], Tile::Air));
+        assert!(matches!(tiles[0][2], Tile::Coal));
+        assert!(matches!(tiles[1][2], Tile::Rock));
+        // outside of the map
+        assert!(matches!(tiles[0][3], Tile::Rock));
+        assert!(matches!(tiles[2][0], Tile::Rock));
+    }
+
+    #[test]
+    fn tile_layers_must_match_their_size() {
+        for layer in [
+            r#"{ "type": "tilelayer", "width": 3, "height": 2, "data": [1, 0, 2, 0, 0, 1, 1] }"#,
+            r#"{ "type": "tilelayer", "width": 0, "height": 2, "data": [1, 0] }"#,
+        ] {
+            let map = MAP.replace(
+                r#"{ "type": "tilelayer", "width": 3, "height": 2, "data": [1, 0, 2, 0, 0, 1] }"#,
+                layer,
+            );
+            let map: TiledMap = serde_json::from_str(&map).unwrap();
+            assert!(map.into_tiles().is_err(), "{layer}");
+        }
+    }
+
+    #[test]
+    fn object_layers() {
+        let map: TiledMap = serde_json::from_str(MAP).unwrap();
+        let (_, triggers) = map.into_tiles().unwrap();
+
+        assert_eq!(triggers.len(), 2);
+
+        let coal = &triggers[0];
+        assert_eq!(coal.trigger_type, TriggerType::ItemPickup);
+        assert_eq!(coal.flag.as_deref(), Some("CoalCollected"));
+        assert_eq!(coal.position, Vec2::new(100.0, 0.0));
+        assert_eq!(coal.size, Vec2::new(100.0, 100.0));
+        assert!(coal.delete_on_trigger);
+
+        let ship = &triggers[1];
+        assert_eq!(ship.trigger_type, TriggerType::Ship);
+        assert_eq!(ship.position, Vec2::new(50.0, -50.0));
+        assert!(!ship.delete_on_trigger);
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+// how far the stick has to be pushed to count as a direction
+const STICK_DEADZONE: f32 = 0.5;
+
+/// Something the player can do, bound to keys and gamepad buttons in [`Bindings`].
+///
+/// Read it like any other button, through `Res<ButtonInput<Action>>`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Jump,
+    Jetpack,
+    Interact,
+    Map,
+    Confirm,
+    Cancel,
+    Pause,
+    QuickSave,
+    AutoAdvance,
+    Skip,
+    Backlog,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Jump,
+        Action::Jetpack,
+        Action::Interact,
+        Action::Map,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Pause,
+        Action::QuickSave,
+        Action::AutoAdvance,
+        Action::Skip,
+        Action::Backlog,
+    ];
+
+    /// Whether both can be read at the same time, so they shouldn't share a key or button.
+    pub fn conflicts_with(self, other: Action) -> bool {
+        [EXPLORING.as_slice(), TALKING.as_slice()]
+            .iter()
+            .any(|actions| actions.contains(&self) && actions.contains(&other))
+    }
+
+    /// The stick direction that also triggers this action.
+    fn stick_direction(self) -> Option<Vec2> {
+        match self {
+            Action::Left => Some(Vec2::NEG_X),
+            Action::Right => Some(Vec2::X),
+            Action::Up => Some(Vec2::Y),
+            Action::Down => Some(Vec2::NEG_Y),
+            _ => None,
+        }
+    }
+}
+
+// what is read while walking around the cave, and in the dialogue and menus
+const EXPLORING: [Action; 9] = [
+    Action::Left,
+    Action::Right,
+    Action::Up,
+    Action::Down,
+    Action::Jump,
+    Action::Jetpack,
+    Action::Interact,
+    Action::Map,
+    Action::Pause,
+];
+const TALKING: [Action; 10] = [
+    Action::Left,
+    Action::Right,
+    Action::Up,
+    Action::Down,
+    Action::Confirm,
+    Action::Cancel,
+    Action::QuickSave,
+    Action::AutoAdvance,
+    Action::Skip,
+    Action::Backlog,
+];
+
+/// The keys and gamepad buttons for every [`Action`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Bindings {
+    pub keys: BTreeMap<Action, Vec<KeyCode>>,
+    pub gamepad: BTreeMap<Action, Vec<GamepadButton>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use GamepadButton as Pad;
+        use KeyCode as Key;
+
+        let defaults = [
+            (
+                Action::Left,
+                vec![Key::KeyA, Key::ArrowLeft],
+                vec![Pad::DPadLeft],
+            ),
+            (
+                Action::Right,
+                vec![Key::KeyD, Key::ArrowRight],
+                vec![Pad::DPadRight],
+            ),
+            (Action::Up, vec![Key::KeyW, Key::ArrowUp], vec![Pad::DPadUp]),
+            (
+                Action::Down,
+                vec![Key::KeyS, Key::ArrowDown],
+                vec![Pad::DPadDown],
+            ),
+            (Action::Jump, vec![Key::Space], vec![Pad::South]),
+            (
+                Action::Jetpack,
+                vec![Key::ShiftLeft, Key::ShiftRight],
+                vec![Pad::RightTrigger2, Pad::RightTrigger],
+            ),
+            (Action::Interact, vec![Key::KeyE], vec![Pad::West]),
+            (Action::Map, vec![Key::KeyM], vec![Pad::North]),
+            (
+                Action::Confirm,
+                vec![Key::Enter, Key::Space, Key::KeyZ],
+                vec![Pad::South],
+            ),
+            (Action::Cancel, vec![Key::Escape], vec![Pad::East]),
+            (Action::Pause, vec![Key::Escape], vec![Pad::Start]),
+            (Action::QuickSave, vec![Key::F5], vec![]),
+            (Action::AutoAdvance, vec![Key::KeyQ], vec![Pad::Select]),
+            (Action::Skip, vec![Key::KeyF], vec![Pad::LeftTrigger]),
+            (Action::Backlog, vec![Key::KeyB], vec![Pad::LeftTrigger2]),
+        ];
+
+        let mut bindings = Bindings {
+            keys: BTreeMap::new(),
+            gamepad: BTreeMap::new(),
+        };
+        for (action, keys, buttons) in defaults {
+            bindings.keys.insert(action, keys);
+            bindings.gamepad.insert(action, buttons);
+        }
+        bindings
+    }
+}
+
+impl Bindings {
+    /// Fills in actions missing from an older settings file with their defaults.
+    pub fn fill_missing(&mut self) {
+        let defaults = Bindings::default();
+        for (action, keys) in defaults.keys {
+            self.keys.entry(action).or_insert(keys);
+        }
+        for (action, buttons) in defaults.gamepad {
+            self.gamepad.entry(action).or_insert(buttons);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButton] {
+        self.gamepad.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `action` to just `key`, giving its old key to whatever it conflicted with.
+    pub fn rebind_key(&mut self, action: Action, key: KeyCode) {
+        rebind(&mut self.keys, action, key);
+    }
+
+    /// Binds `action` to just `button`, giving its old button to whatever it conflicted with.
+    pub fn rebind_button(&mut self, action: Action, button: GamepadButton) {
+        rebind(&mut self.gamepad, action, button);
+    }
+
+    fn pressed<'a>(
+        &self,
+        action: Action,
+        keyboard: &ButtonInput<KeyCode>,
+        mut gamepads: impl Iterator<Item = &'a Gamepad>,
+    ) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+            || gamepads.any(|gamepad| {
+                gamepad.any_pressed(self.buttons(action).iter().copied())
+                    || action.stick_direction().is_some_and(|direction| {
+                        gamepad.left_stick().dot(direction) > STICK_DEADZONE
+                    })
+            })
+    }
+}
+
+fn rebind<T: Copy + PartialEq>(bindings: &mut BTreeMap<Action, Vec<T>>, action: Action, input: T) {
+    let old = bindings.insert(action, vec![input]).unwrap_or_default();
+    for (&other, inputs) in bindings.iter_mut() {
+        if other == action || !action.conflicts_with(other) {
+            continue;
+        }
+        let Some(slot) = inputs.iter().position(|&bound| bound == input) else {
+            continue;
+        };
+        match old.first() {
+            Some(&swapped) if !inputs.contains(&swapped) => inputs[slot] = swapped,
+            _ => {
+                inputs.remove(slot);
+            }
+        }
+    }
+}
+
+pub fn input_plugin(app: &mut App) {
+    app.init_resource::<ButtonInput<Action>>()
+        .add_systems(PreUpdate, update_actions.after(InputSystem));
+}
+
+pub fn update_actions(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    settings: Res<Settings>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        if settings
+            .bindings
+            .pressed(action, &keyboard, gamepads.iter())
+        {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+/// A short name for a key, for showing bindings to the player.
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix).filter(|rest| !rest.is_empty()))
+        .map_or(name.clone(), str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_actions_get_their_defaults() {
+        let mut bindings: Bindings =
+            serde_json::from_str(r#"{"keys": {"Jump": ["KeyK"]}, "gamepad": {}}"#).unwrap();
+        bindings.fill_missing();
+
+        assert_eq!(bindings.keys(Action::Jump), &[KeyCode::KeyK]);
+        assert_eq!(bindings.keys(Action::Map), &[KeyCode::KeyM]);
+        assert_eq!(bindings.buttons(Action::Jump), &[GamepadButton::South]);
+    }
+
+    #[test]
+    fn rebinding_swaps_conflicting_bindings() {
+        let mut bindings = Bindings::default();
+        for a in Action::ALL {
+            for b in Action::ALL
+                .into_iter()
+                .filter(|&b| a != b && a.conflicts_with(b))
+            {
+                for key in bindings.keys(a) {
+                    assert!(!bindings.keys(b).contains(key), "{a:?} and {b:?}");
+                }
+                for button in bindings.buttons(a) {
+                    assert!(!bindings.buttons(b).contains(button), "{a:?} and {b:?}");
+                }
+            }
+        }
+
+        bindings.rebind_key(Action::Jump, KeyCode::KeyE);
+        assert_eq!(bindings.keys(Action::Jump), &[KeyCode::KeyE]);
+        assert_eq!(bindings.keys(Action::Interact), &[KeyCode::Space]);
+        // confirming isn't read while exploring, so it can keep sharing space
+        assert!(bindings.keys(Action::Confirm).contains(&KeyCode::Space));
+
+        // the ship screen moves its cursor sideways while dialogue keys are live
+        bindings.rebind_key(Action::Skip, KeyCode::KeyA);
+        assert_eq!(
+            bindings.keys(Action::Left),
+            &[KeyCode::KeyF, KeyCode::ArrowLeft]
+        );
+
+        bindings.rebind_button(Action::Backlog, GamepadButton::South);
+        assert_eq!(
+            bindings.buttons(Action::Confirm),
+            &[GamepadButton::LeftTrigger2]
+        );
+        assert_eq!(bindings.buttons(Action::Jump), &[GamepadButton::South]);
+    }
+
+    #[test]
+    fn stick_moves_only_past_the_deadzone() {
+        let keyboard = ButtonInput::<KeyCode>::default();
+        let bindings = Bindings::default();
+        let mut gamepad = Gamepad::default();
+
+        gamepad.analog_mut().set(GamepadAxis::LeftStickX, 0.3);
+        assert!(!bindings.pressed(Action::Right, &keyboard, [&gamepad].into_iter()));
+
+        gamepad.analog_mut().set(GamepadAxis::LeftStickX, 0.9);
+        assert!(bindings.pressed(Action::Right, &keyboard, [&gamepad].into_iter()));
+        assert!(!bindings.pressed(Action::Left, &keyboard, [&gamepad].into_iter()));
+    }
+}
@@ -1,27 +1,40 @@
-use crate::dating_sim::DatingScene;
+use crate::dating_sim::{
+    markup,
+    roster::{self, Character},
+    DatingScene,
+};
 //use serde::Deserialize;
 
-pub fn load_scenes() -> Vec<DatingScene> {
-    let json_file_path = std::path::Path::new("assets/Scenes/GenericScenes.json");
+pub fn load_characters() -> Vec<Character> {
+    let file = std::fs::File::open("assets/characters.json").expect("failed to open file");
+    serde_json::from_reader(file).expect("error while reading or parsing")
+}
 
-    let paths = vec![
-        std::path::Path::new("assets/Scenes/GenericScenes.json"),
-        std::path::Path::new("assets/Scenes/DiedrickScenes.json"),
-        std::path::Path::new("assets/Scenes/FredrickScenes.json"),
-        std::path::Path::new("assets/Scenes/JoeScenes.json"),
-        std::path::Path::new("assets/Scenes/JuleScenes.json"),
-        std::path::Path::new("assets/Scenes/CarleScenes.json"),
-        std::path::Path::new("assets/Scenes/LivScenes.json"),
-        std::path::Path::new("assets/Scenes/CatScenes.json"),
-    ];
+/// The generic scenes, then each character's.
+pub fn load_scenes(characters: &[Character]) -> Vec<DatingScene> {
+    let files = std::iter::once("GenericScenes.json")
+        .chain(characters.iter().filter_map(|c| c.scene_file.as_deref()));
 
     let mut scenes: Vec<DatingScene> = vec![];
-    for path in paths {
+    for file in files {
+        let path = std::path::Path::new("assets/Scenes").join(file);
         let file = std::fs::File::open(path).expect("failed to open file");
 
         let scenes_one_file: Vec<DatingScene> =
             serde_json::from_reader(file).expect("error while reading or parsing");
         scenes.append(&mut scenes_one_file.clone());
     }
+
+    let errors = markup::markup_errors(&scenes);
+    if !errors.is_empty() {
+        panic!("broken markup in scenes:\n{}", errors.join("\n"));
+    }
+    let unknown = roster::unknown_speakers(&scenes, characters);
+    if !unknown.is_empty() {
+        panic!(
+            "scenes spoken by unknown characters:\n{}",
+            unknown.join("\n")
+        );
+    }
     scenes
 }
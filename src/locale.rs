@@ -0,0 +1,273 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    dating_sim::{self, markup},
+    load,
+};
+
+/// What everything is written in first, and falls back to.
+pub const DEFAULT_LANGUAGE: &str = "en";
+// one folder per language, with ui.ftl and optionally scenes.json in it
+const LOCALES: &str = "assets/Locales";
+
+/// Text given as is, or per language: `"Joe"` or `{"en": "Joe", "de": "Hausmeister Joe"}`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Localized {
+    Plain(String),
+    ByLanguage(HashMap<String, String>),
+}
+
+impl Localized {
+    /// The text in `language`, or in the default language if it hasn't been translated.
+    pub fn get(&self, language: &str) -> &str {
+        match self {
+            Localized::Plain(text) => text,
+            Localized::ByLanguage(texts) => texts
+                .get(language)
+                .or_else(|| texts.get(DEFAULT_LANGUAGE))
+                .map_or("", String::as_str),
+        }
+    }
+}
+
+/// A scene's text in another language, by where it is in the English scene.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SceneTranslation {
+    pub lines: BTreeMap<usize, String>,
+    pub choices: BTreeMap<usize, String>,
+    /// The reasons shown on locked choices.
+    pub locked: BTreeMap<usize, String>,
+}
+
+/// The UI strings and scene text of the language picked in the settings.
+///
+/// Anything the translation leaves out is shown in English.
+#[derive(Resource, Debug, Default)]
+pub struct Locale {
+    language: String,
+    ui: HashMap<String, String>,
+    scenes: HashMap<String, SceneTranslation>,
+}
+
+impl Locale {
+    pub fn load(language: &str) -> Locale {
+        let mut ui = read_ui(DEFAULT_LANGUAGE).unwrap_or_else(|e| {
+            log::error!("could not read the {DEFAULT_LANGUAGE} UI strings: {e:#}");
+            HashMap::new()
+        });
+        if language != DEFAULT_LANGUAGE {
+            match read_ui(language) {
+                Ok(translated) => ui.extend(translated),
+                Err(e) => log::error!("could not read the {language} UI strings: {e:#}"),
+            }
+        }
+        let scenes = read_scenes(language).unwrap_or_else(|e| {
+            log::error!("could not read the {language} scenes: {e:#}");
+            HashMap::new()
+        });
+        for error in markup::translation_markup_errors(&scenes) {
+            log::error!("broken markup in the {language} scenes: {error}");
+        }
+
+        Locale {
+            language: language.to_string(),
+            ui,
+            scenes,
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The UI string `key`, or the key itself if no language has it.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.ui.get(key).map_or(key, String::as_str)
+    }
+
+    /// The UI string `key` with each `{ $name }` in it filled in from `args`.
+    ///
+    /// Spacing inside the braces doesn't matter, placeables without an argument are left as is.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut text = String::new();
+        let mut rest = self.text(key);
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                break;
+            };
+            text.push_str(&rest[..start]);
+            let value = rest[start + 1..end]
+                .trim()
+                .strip_prefix('$')
+                .and_then(|name| args.iter().find(|(arg, _)| *arg == name));
+            match value {
+                Some((_, value)) => text.push_str(&value.to_string()),
+                None => text.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+        text
+    }
+
+    /// Line `index` of `scene`, or `english` if it hasn't been translated.
+    pub fn line<'a>(&'a self, scene: &str, index: usize, english: &'a str) -> &'a str {
+        self.scene_text(scene, english, |scene| scene.lines.get(&index))
+    }
+
+    /// Choice `index` of `scene`, or `english` if it hasn't been translated.
+    pub fn choice<'a>(&'a self, scene: &str, index: usize, english: &'a str) -> &'a str {
+        self.scene_text(scene, english, |scene| scene.choices.get(&index))
+    }
+
+    /// Why choice `index` of `scene` is locked, or `english` if it hasn't been translated.
+    pub fn locked<'a>(&'a self, scene: &str, index: usize, english: &'a str) -> &'a str {
+        self.scene_text(scene, english, |scene| scene.locked.get(&index))
+    }
+
+    fn scene_text<'a>(
+        &'a self,
+        scene: &str,
+        english: &'a str,
+        pick: impl Fn(&'a SceneTranslation) -> Option<&'a String>,
+    ) -> &'a str {
+        self.scenes
+            .get(scene)
+            .and_then(pick)
+            .map_or(english, String::as_str)
+    }
+}
+
+/// Every language with a folder in `assets/Locales`, in order.
+pub fn languages() -> Vec<String> {
+    let mut languages = fs::read_dir(LOCALES)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    languages.sort();
+    languages
+}
+
+fn folder(language: &str) -> PathBuf {
+    Path::new(LOCALES).join(language)
+}
+
+fn read_ui(language: &str) -> anyhow::Result<HashMap<String, String>> {
+    let path = folder(language).join("ui.ftl");
+    let source =
+        fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    parse_ftl(&source).with_context(|| format!("in {}", path.display()))
+}
+
+// a language without a scenes.json just hasn't translated any yet
+fn read_scenes(language: &str) -> anyhow::Result<HashMap<String, SceneTranslation>> {
+    let path = folder(language).join("scenes.json");
+    match fs::read_to_string(&path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(_) => Ok(HashMap::new()),
+    }
+}
+
+/// Reads the `key = value` messages of a Fluent file, skipping `#` comments.
+///
+/// Only single line messages are understood, with `{ $name }` placeables left for [`Locale::format`].
+fn parse_ftl(source: &str) -> anyhow::Result<HashMap<String, String>> {
+    let mut messages = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("line {}: expected `key = value`", number + 1);
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            bail!("line {}: bad key {key:?}", number + 1);
+        }
+        if messages
+            .insert(key.to_string(), value.trim().to_string())
+            .is_some()
+        {
+            bail!("line {}: {key} is given twice", number + 1);
+        }
+    }
+    Ok(messages)
+}
+
+/// Everything `language` is missing next to English, saying where it is.
+pub fn untranslated(language: &str) -> anyhow::Result<Vec<String>> {
+    let english = read_ui(DEFAULT_LANGUAGE)?;
+    let translated = read_ui(language)?;
+    let mut missing = english
+        .keys()
+        .filter(|key| !translated.contains_key(*key))
+        .map(|key| format!("ui: {key}"))
+        .collect::<Vec<_>>();
+    missing.sort();
+
+    let characters = load::load_characters();
+    let scenes = load::load_scenes(&characters);
+    missing.extend(dating_sim::untranslated_text(
+        &scenes,
+        &read_scenes(language)?,
+    ));
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untranslated_text_falls_back() {
+        let name: Localized =
+            serde_json::from_str(r#"{"en": "Joe", "de": "Hausmeister Joe"}"#).unwrap();
+        assert_eq!(name.get("de"), "Hausmeister Joe");
+        assert_eq!(name.get("fr"), "Joe");
+
+        let name: Localized = serde_json::from_str(r#""Liv""#).unwrap();
+        assert_eq!(name.get("de"), "Liv");
+    }
+
+    #[test]
+    fn ui_strings_are_read_and_filled_in() {
+        let ui = parse_ftl(
+            "# the heads up display\nhud-poles = Poles: { $poles }\n\nhud-return = Go = now!\nhud-oxygen = {$current}/{ $max}s { $unknown }\n",
+        )
+        .unwrap();
+        let locale = Locale { ui, ..default() };
+        assert_eq!(locale.format("hud-poles", &[("poles", &3)]), "Poles: 3");
+        assert_eq!(
+            locale.format("hud-oxygen", &[("current", &12), ("max", &60)]),
+            "12/60s { $unknown }"
+        );
+        assert_eq!(locale.text("hud-return"), "Go = now!");
+        assert_eq!(locale.text("hud-missing"), "hud-missing");
+
+        assert!(parse_ftl("no value here").is_err());
+        assert!(parse_ftl("a = 1\na = 2").is_err());
+    }
+
+    #[test]
+    fn every_language_has_its_own_name() {
+        for language in languages() {
+            let ui = read_ui(&language).unwrap();
+            assert!(ui.contains_key("language-name"), "{language}");
+        }
+    }
+}
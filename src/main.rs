@@ -9,7 +9,14 @@ use bevy::{
 
 mod dating_sim;
 mod game;
+mod input;
 mod load;
+mod locale;
+mod menu;
+mod random;
+mod save;
+mod settings;
+mod ui;
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum GameState {
@@ -20,14 +27,36 @@ enum GameState {
 }
 
 fn main() {
+    // `--untranslated de` lists what the German translation is missing instead of playing
+    if let Some(language) = arg_value("--untranslated") {
+        match locale::untranslated(&language) {
+            Ok(missing) => {
+                for entry in &missing {
+                    println!("{entry}");
+                }
+                println!("{} untranslated in {language}", missing.len());
+            }
+            Err(e) => {
+                eprintln!("could not check {language}: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     App::new()
         .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
         .insert_resource(WinitSettings::desktop_app())
+        .insert_resource(random::Random::from_args())
         .add_systems(Startup, setup)
         .add_plugins((
             menu::menu_plugin,
             game::game_plugin,
             dating_sim::dating_sim_plugin,
+            save::save_plugin,
+            ui::ui_plugin,
+            settings::settings_plugin,
+            input::input_plugin,
         ))
         .init_state::<GameState>()
         .add_systems(OnEnter(GameState::DatingSim), set_winit_dating)
@@ -35,6 +64,20 @@ fn main() {
         .run();
 }
 
+/// The value following `name` on the command line, e.g. `--seed 42`.
+pub fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
+
+/// Where `path` in the assets folder is, wherever the asset server looks for it.
+pub fn asset_path(path: &str) -> std::path::PathBuf {
+    bevy::asset::io::file::FileAssetReader::get_base_path()
+        .join("assets")
+        .join(path)
+}
+
 fn set_winit_dating(mut winit: ResMut<WinitSettings>) {
     winit.focused_mode = UpdateMode::reactive_low_power(Duration::from_secs(1));
     winit.unfocused_mode = UpdateMode::reactive_low_power(Duration::from_secs(1));
@@ -45,14 +88,13 @@ fn set_winit_explore(mut winit: ResMut<WinitSettings>) {
     winit.unfocused_mode = UpdateMode::Continuous;
 }
 
-fn setup(
-    mut commands: Commands,
-    mut menu_state: ResMut<NextState<GameState>>,
-    mut dating_state: ResMut<NextState<dating_sim::DatingState>>,
-) {
+fn setup(mut commands: Commands, mut menu_state: ResMut<NextState<GameState>>) {
     commands.spawn(Camera2d).insert(Transform::default());
-    menu_state.set(GameState::DatingSim);
-    dating_state.set(dating_sim::DatingState::Chilling);
+
+    // a save given on the command line skips the title screen
+    if arg_value("--load").is_some() {
+        menu_state.set(GameState::DatingSim);
+    }
 }
 
 fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
@@ -60,25 +102,3 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
         commands.entity(entity).despawn_recursive();
     }
 }
-
-mod menu {
-    use super::GameState;
-    use bevy::prelude::*;
-
-    pub fn menu_plugin(app: &mut App) {
-        app.add_systems(Update, change_scene.run_if(in_state(GameState::Menu)));
-    }
-
-    pub fn change_scene(
-        keys: Res<ButtonInput<KeyCode>>,
-        mut menu_state: ResMut<NextState<GameState>>,
-    ) {
-        if keys.just_pressed(KeyCode::KeyD) {
-            menu_state.set(GameState::DatingSim);
-            println!("going dating sim mode");
-        } else if keys.just_pressed(KeyCode::KeyE) {
-            menu_state.set(GameState::Explore);
-            println!("going exploration mode");
-        }
-    }
-}
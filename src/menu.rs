@@ -0,0 +1,260 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    dating_sim::DatingContext,
+    despawn_screen,
+    game::fog::ExploredMaps,
+    input::Action,
+    locale::Locale,
+    random::Random,
+    save,
+    settings::{Music, SettingsMenu},
+    ui::{self, Disabled, MenuActivated, MenuList},
+    GameState,
+};
+
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Menu)]
+enum MenuScreen {
+    #[default]
+    Title,
+    Load,
+    Credits,
+}
+
+/// The background, shown behind every menu screen.
+#[derive(Component)]
+struct OnMenu;
+
+/// The panel of the current [`MenuScreen`].
+#[derive(Component)]
+struct OnMenuScreen;
+
+#[derive(Component, Clone, Debug)]
+enum MenuAction {
+    NewGame,
+    Continue,
+    Load,
+    Settings,
+    Credits,
+    Quit,
+    LoadSave(PathBuf),
+    Back,
+}
+
+pub fn menu_plugin(app: &mut App) {
+    app.add_sub_state::<MenuScreen>()
+        .add_systems(OnEnter(GameState::Menu), spawn_background)
+        .add_systems(OnExit(GameState::Menu), despawn_screen::<OnMenu>)
+        .add_systems(OnEnter(MenuScreen::Title), spawn_title)
+        .add_systems(OnEnter(MenuScreen::Load), spawn_load)
+        .add_systems(OnEnter(MenuScreen::Credits), spawn_credits)
+        .add_systems(OnExit(MenuScreen::Title), despawn_screen::<OnMenuScreen>)
+        .add_systems(OnExit(MenuScreen::Load), despawn_screen::<OnMenuScreen>)
+        .add_systems(OnExit(MenuScreen::Credits), despawn_screen::<OnMenuScreen>)
+        .add_systems(
+            Update,
+            (menu_action, back_to_title)
+                .run_if(in_state(GameState::Menu).and(in_state(SettingsMenu::Closed))),
+        );
+}
+
+fn spawn_background(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        ImageNode::new(asset_server.load("Backgrounds/deeper_deeper_base.png")),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            ..default()
+        },
+        GlobalZIndex(-1),
+        OnMenu,
+    ));
+}
+
+/// A full screen column with a heading, for the buttons of a screen to go under.
+fn spawn_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    heading: &str,
+    contents: impl FnOnce(&mut ChildBuilder, &Handle<Font>),
+) {
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+    let bold = asset_server.load("fonts/Pixelfont/slkscrb.ttf");
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(40.),
+                ..default()
+            },
+            OnMenuScreen,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new(heading),
+                TextFont {
+                    font: bold,
+                    font_size: 80.0,
+                    ..default()
+                },
+            ));
+            contents(builder, &font);
+        });
+}
+
+fn spawn_title(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+    let has_save = save::latest().is_some();
+
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        locale.text("menu-title"),
+        |builder, font| {
+            builder
+                .spawn((ui::menu_column(), MenuList::default()))
+                .with_children(|builder| {
+                    let items = [
+                        ("menu-new-game", MenuAction::NewGame, true),
+                        ("menu-continue", MenuAction::Continue, has_save),
+                        ("menu-load", MenuAction::Load, has_save),
+                        ("menu-settings", MenuAction::Settings, true),
+                        ("menu-credits", MenuAction::Credits, true),
+                        ("menu-quit", MenuAction::Quit, true),
+                    ];
+                    for (index, (key, action, enabled)) in items.into_iter().enumerate() {
+                        let label = locale.text(key);
+                        if enabled {
+                            ui::spawn_menu_item(builder, font, index, label, action);
+                        } else {
+                            ui::spawn_menu_item(builder, font, index, label, (action, Disabled));
+                        }
+                    }
+                });
+        },
+    );
+}
+
+fn spawn_load(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+    let saves = save::list();
+
+    let heading = locale.text("menu-load");
+    spawn_screen(&mut commands, &asset_server, heading, |builder, font| {
+        builder
+            .spawn((ui::menu_column(), MenuList::default()))
+            .with_children(|builder| {
+                for (index, path) in saves.iter().enumerate() {
+                    let name = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    ui::spawn_menu_item(
+                        builder,
+                        font,
+                        index,
+                        name,
+                        MenuAction::LoadSave(path.clone()),
+                    );
+                }
+                ui::spawn_menu_item(
+                    builder,
+                    font,
+                    saves.len(),
+                    locale.text("menu-back"),
+                    MenuAction::Back,
+                );
+            });
+    });
+}
+
+fn spawn_credits(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+    let path = crate::asset_path("credits.txt");
+    let credits = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        log::error!("could not read {}: {e}", path.display());
+        String::new()
+    });
+
+    commands.spawn((
+        AudioPlayer::new(asset_server.load("Music/Music_End_credits.ogg")),
+        Music,
+        OnMenuScreen,
+    ));
+
+    let heading = locale.text("menu-credits");
+    spawn_screen(&mut commands, &asset_server, heading, |builder, font| {
+        builder.spawn((
+            Text::new(credits),
+            TextFont {
+                font: font.clone(),
+                font_size: 30.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+        ));
+        builder
+            .spawn((ui::menu_column(), MenuList::default()))
+            .with_children(|builder| {
+                ui::spawn_menu_item(builder, font, 0, locale.text("menu-back"), MenuAction::Back);
+            });
+    });
+}
+
+fn back_to_title(actions: Res<ButtonInput<Action>>, mut screen: ResMut<NextState<MenuScreen>>) {
+    if actions.just_pressed(Action::Cancel) {
+        screen.set(MenuScreen::Title);
+    }
+}
+
+fn menu_action(
+    mut reader: EventReader<MenuActivated>,
+    actions: Query<&MenuAction>,
+    mut screen: ResMut<NextState<MenuScreen>>,
+    mut menu_state: ResMut<NextState<GameState>>,
+    mut settings_menu: ResMut<NextState<SettingsMenu>>,
+    mut context: ResMut<DatingContext>,
+    mut explored: ResMut<ExploredMaps>,
+    mut random: ResMut<Random>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for MenuActivated(entity) in reader.read() {
+        let Ok(action) = actions.get(*entity) else {
+            continue;
+        };
+
+        match action {
+            MenuAction::NewGame => menu_state.set(GameState::DatingSim),
+            MenuAction::Load => screen.set(MenuScreen::Load),
+            MenuAction::Settings => settings_menu.set(SettingsMenu::Open),
+            MenuAction::Credits => screen.set(MenuScreen::Credits),
+            MenuAction::Back => screen.set(MenuScreen::Title),
+            MenuAction::Quit => {
+                exit.send(AppExit::Success);
+            }
+            MenuAction::Continue | MenuAction::LoadSave(_) => {
+                let path = match action {
+                    MenuAction::LoadSave(path) => Some(path.clone()),
+                    _ => save::latest(),
+                };
+                let Some(path) = path else {
+                    continue;
+                };
+
+                match save::read(&path) {
+                    Ok(data) => {
+                        save::restore(&data, &mut context, &mut explored, &mut random);
+                        menu_state.set(GameState::DatingSim);
+                    }
+                    Err(e) => log::error!("could not load save {}: {e}", path.display()),
+                }
+            }
+        }
+    }
+}
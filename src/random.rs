@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+const DEFAULT_SEED: u64 = 0xDADA_DADA_DADA_DADA;
+
+/// All the randomness in the game, split into independent streams.
+///
+/// Every stream is derived from the same seed, so drawing more numbers from one
+/// system's stream never changes what another system gets.
+#[derive(Resource)]
+pub struct Random {
+    pub seed: u64,
+    pub world: StdRng,
+    pub audio: StdRng,
+    /// Where things turn up, like pickups and oxygen canisters.
+    pub events: StdRng,
+}
+
+impl Random {
+    pub fn from_seed(seed: u64) -> Random {
+        let stream =
+            |index: u64| StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+        Random {
+            seed,
+            world: stream(1),
+            audio: stream(2),
+            events: stream(3),
+        }
+    }
+
+    /// Uses `--seed <n>`, then the seed of the save given with `--load <path>`.
+    pub fn from_args() -> Random {
+        let seed = crate::arg_value("--seed")
+            .map(|seed| {
+                seed.parse().unwrap_or_else(|_| {
+                    log::error!("--seed must be a number, not {seed:?}, using a random one");
+                    rand::random()
+                })
+            })
+            .or_else(|| {
+                let path = crate::arg_value("--load")?;
+                crate::save::read(path.as_ref()).ok().map(|save| save.seed)
+            })
+            .unwrap_or(DEFAULT_SEED);
+
+        log::info!("using seed {seed}");
+        Random::from_seed(seed)
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random::from_seed(DEFAULT_SEED)
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dating_sim::{backlog::BacklogEntry, read_lines, DatingContext, DatingState, MissionType},
+    game::fog::ExploredMaps,
+    input::Action,
+    random::Random,
+};
+
+/// Everything needed to pick a game back up.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveData {
+    pub seed: u64,
+    #[serde(default)]
+    pub flags: HashMap<String, isize>,
+    #[serde(default)]
+    pub missions: Vec<MissionType>,
+    /// Run length encoded explored cells per map.
+    #[serde(default)]
+    pub explored: HashMap<String, Vec<u32>>,
+    #[serde(default)]
+    pub backlog: Vec<BacklogEntry>,
+    #[serde(default)]
+    pub captain: Option<String>,
+}
+
+pub fn save_plugin(app: &mut App) {
+    app.add_systems(Startup, load_from_args)
+        .add_systems(
+            OnEnter(DatingState::Chilling),
+            (autosave, read_lines::save_read_lines),
+        )
+        .add_systems(
+            Update,
+            (quicksave, read_lines::save_read_lines)
+                .chain()
+                .run_if(in_state(DatingState::Chilling).and(quicksave_pressed)),
+        );
+}
+
+pub fn save_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("DeeperAndDeeper")
+        .join("saves")
+}
+
+pub fn read(path: &Path) -> anyhow::Result<SaveData> {
+    let file = fs::File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+pub fn write(name: &str, data: &SaveData) -> anyhow::Result<PathBuf> {
+    let dir = save_dir();
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{name}.json"));
+    fs::write(&path, serde_json::to_string_pretty(data)?)?;
+    Ok(path)
+}
+
+/// All saves, newest first.
+pub fn list() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(save_dir()) else {
+        return vec![];
+    };
+
+    let mut saves = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect::<Vec<_>>();
+    saves.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    saves.into_iter().map(|(_, path)| path).collect()
+}
+
+pub fn latest() -> Option<PathBuf> {
+    list().into_iter().next()
+}
+
+fn snapshot(context: &DatingContext, random: &Random, explored: &ExploredMaps) -> SaveData {
+    SaveData {
+        explored: explored.to_save(),
+        ..context.to_save(random.seed)
+    }
+}
+
+/// Puts the game back into the state of `data`.
+pub fn restore(
+    data: &SaveData,
+    context: &mut DatingContext,
+    explored: &mut ExploredMaps,
+    random: &mut Random,
+) {
+    context.restore(data);
+    explored.restore(&data.explored);
+    if random.seed != data.seed {
+        *random = Random::from_seed(data.seed);
+    }
+}
+
+fn load_from_args(
+    mut context: ResMut<DatingContext>,
+    mut explored: ResMut<ExploredMaps>,
+    mut random: ResMut<Random>,
+) {
+    let Some(path) = crate::arg_value("--load") else {
+        return;
+    };
+
+    match read(path.as_ref()) {
+        Ok(data) => restore(&data, &mut context, &mut explored, &mut random),
+        Err(e) => log::error!("could not load save {path}: {e}"),
+    }
+}
+
+fn autosave(context: Res<DatingContext>, random: Res<Random>, explored: Res<ExploredMaps>) {
+    if let Err(e) = write("autosave", &snapshot(&context, &random, &explored)) {
+        log::error!("autosave failed: {e}");
+    }
+}
+
+fn quicksave_pressed(actions: Res<ButtonInput<Action>>) -> bool {
+    actions.just_pressed(Action::QuickSave)
+}
+
+fn quicksave(context: Res<DatingContext>, random: Res<Random>, explored: Res<ExploredMaps>) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    match write(
+        &format!("save-{time}"),
+        &snapshot(&context, &random, &explored),
+    ) {
+        Ok(path) => log::info!("saved to {}", path.display()),
+        Err(e) => log::error!("quicksave failed: {e}"),
+    }
+}
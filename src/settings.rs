@@ -0,0 +1,614 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_screen,
+    input::{self, Action, Bindings},
+    locale::{self, Locale},
+    ui::{self, MenuActivated, MenuList},
+};
+
+const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+// characters per second, 0 shows the whole line at once, with the key of their name
+const TEXT_SPEEDS: [(f32, &str); 4] = [
+    (20.0, "text-speed-slow"),
+    (40.0, "text-speed-normal"),
+    (80.0, "text-speed-fast"),
+    (0.0, "text-speed-instant"),
+];
+// confirm and cancel are left out so the menus can't be locked out of
+const REBINDABLE: [Action; 8] = [
+    Action::Left,
+    Action::Right,
+    Action::Up,
+    Action::Down,
+    Action::Jump,
+    Action::Jetpack,
+    Action::Interact,
+    Action::Map,
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowSetting {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+/// Player preferences, kept in `settings.json` in the config dir.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// 0 to 1.
+    pub music_volume: f32,
+    /// 0 to 1.
+    pub sfx_volume: f32,
+    pub window: WindowSetting,
+    pub resolution: (u32, u32),
+    /// Characters per second for dialogue, 0 for instant.
+    pub text_speed: f32,
+    pub show_diagnostics: bool,
+    pub bindings: Bindings,
+    /// Which translation of names and dialogue to use, falling back to English.
+    pub language: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            window: WindowSetting::Windowed,
+            resolution: (1280, 720),
+            text_speed: 40.0,
+            show_diagnostics: false,
+            bindings: Bindings::default(),
+            language: locale::DEFAULT_LANGUAGE.to_string(),
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("DeeperAndDeeper")
+            .join("settings.json")
+    }
+
+    /// The saved settings, or the defaults if there are none yet.
+    pub fn load() -> Settings {
+        let path = Settings::path();
+        match fs::read_to_string(&path) {
+            Ok(json) => {
+                let mut settings: Settings = serde_json::from_str(&json).unwrap_or_else(|e| {
+                    log::error!("could not read {}: {e}", path.display());
+                    Settings::default()
+                });
+                settings.bindings.fill_missing();
+                settings
+            }
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Settings::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Marks an [`AudioPlayer`] as music rather than a sound effect.
+#[derive(Component)]
+pub struct Music;
+
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+pub enum SettingsMenu {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(SettingsMenu = SettingsMenu::Open)]
+enum SettingsPage {
+    #[default]
+    Main,
+    Controls,
+}
+
+#[derive(Component)]
+struct OnSettingsMenu;
+
+/// The action waiting for a new key or button on the controls page.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+/// A menu the settings are shown on top of, hidden until they close.
+#[derive(Component)]
+struct HiddenBySettings;
+
+#[derive(Component, Clone, Copy, Debug)]
+enum SettingsRow {
+    MusicVolume,
+    SfxVolume,
+    Window,
+    Resolution,
+    TextSpeed,
+    Language,
+    Diagnostics,
+    Controls,
+    Back,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+enum ControlsRow {
+    Rebind(Action),
+    Reset,
+    Back,
+}
+
+pub fn settings_plugin(app: &mut App) {
+    let settings = Settings::load();
+    app.insert_resource(Locale::load(&settings.language))
+        .insert_resource(settings)
+        .init_state::<SettingsMenu>()
+        .add_sub_state::<SettingsPage>()
+        .init_resource::<Rebinding>()
+        .add_systems(
+            Update,
+            (
+                apply_window.run_if(resource_changed::<Settings>),
+                apply_language
+                    .run_if(resource_changed::<Settings>)
+                    .before(update_labels),
+                apply_volume,
+            ),
+        )
+        .add_systems(OnEnter(SettingsMenu::Open), hide_menus)
+        .add_systems(OnExit(SettingsMenu::Open), close_settings_menu)
+        .add_systems(OnEnter(SettingsPage::Main), spawn_main_page)
+        .add_systems(OnEnter(SettingsPage::Controls), spawn_controls_page)
+        .add_systems(OnExit(SettingsPage::Main), despawn_screen::<OnSettingsMenu>)
+        .add_systems(
+            OnExit(SettingsPage::Controls),
+            (despawn_screen::<OnSettingsMenu>, stop_rebinding),
+        )
+        .add_systems(
+            PreUpdate,
+            capture_binding
+                .after(input::update_actions)
+                .run_if(in_state(SettingsPage::Controls)),
+        )
+        .add_systems(
+            Update,
+            (settings_action, update_labels)
+                .chain()
+                .run_if(in_state(SettingsPage::Main)),
+        )
+        .add_systems(
+            Update,
+            (controls_action, update_control_labels)
+                .chain()
+                .run_if(in_state(SettingsPage::Controls)),
+        );
+}
+
+fn apply_window(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied: Local<Option<(WindowSetting, (u32, u32))>>,
+) {
+    // other settings changing shouldn't undo the player resizing the window by hand
+    let wanted = (settings.window, settings.resolution);
+    if *applied == Some(wanted) {
+        return;
+    }
+    let Ok(mut window) = window.get_single_mut() else {
+        return;
+    };
+    *applied = Some(wanted);
+
+    window.mode = match settings.window {
+        WindowSetting::Windowed => WindowMode::Windowed,
+        WindowSetting::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+        WindowSetting::Fullscreen => WindowMode::Fullscreen(MonitorSelection::Current),
+    };
+    let (width, height) = settings.resolution;
+    window.resolution.set(width as f32, height as f32);
+}
+
+fn apply_language(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    if locale.language() != settings.language {
+        *locale = Locale::load(&settings.language);
+    }
+}
+
+fn apply_volume(
+    settings: Res<Settings>,
+    sinks: Query<(Ref<AudioSink>, Option<&PlaybackSettings>, Has<Music>)>,
+) {
+    for (sink, playback, is_music) in &sinks {
+        if !sink.is_added() && !settings.is_changed() {
+            continue;
+        }
+        let volume = if is_music {
+            settings.music_volume
+        } else {
+            settings.sfx_volume
+        };
+        // on top of any volume the sound was spawned with
+        sink.set_volume(volume * playback.map_or(1.0, |playback| playback.volume.get()));
+    }
+}
+
+fn hide_menus(mut commands: Commands, menus: Query<Entity, With<MenuList>>) {
+    // only one list of buttons should listen to input at a time
+    for menu in &menus {
+        commands
+            .entity(menu)
+            .insert((Visibility::Hidden, HiddenBySettings));
+    }
+}
+
+/// A full screen overlay with a heading over a list of buttons.
+fn spawn_page(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    heading: &str,
+    items: impl FnOnce(&mut ChildBuilder, &Handle<Font>),
+) {
+    let font = asset_server.load("fonts/Pixelfont/slkscr.ttf");
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            GlobalZIndex(20),
+            OnSettingsMenu,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new(heading),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 60.0,
+                    ..default()
+                },
+            ));
+
+            builder
+                .spawn((ui::menu_column(), MenuList::default()))
+                .with_children(|builder| items(builder, &font));
+        });
+}
+
+fn spawn_main_page(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+    let heading = locale.text("settings-title");
+    spawn_page(&mut commands, &asset_server, heading, |builder, font| {
+        let rows = [
+            SettingsRow::MusicVolume,
+            SettingsRow::SfxVolume,
+            SettingsRow::Window,
+            SettingsRow::Resolution,
+            SettingsRow::TextSpeed,
+            SettingsRow::Language,
+            SettingsRow::Diagnostics,
+            SettingsRow::Controls,
+            SettingsRow::Back,
+        ];
+        for (index, row) in rows.into_iter().enumerate() {
+            // the labels are filled in by update_labels
+            ui::spawn_menu_item(builder, font, index, "", row);
+        }
+    });
+}
+
+fn spawn_controls_page(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
+    let heading = locale.text("controls-title");
+    spawn_page(&mut commands, &asset_server, heading, |builder, font| {
+        let rows = REBINDABLE
+            .map(ControlsRow::Rebind)
+            .into_iter()
+            .chain([ControlsRow::Reset, ControlsRow::Back]);
+        for (index, row) in rows.enumerate() {
+            // the labels are filled in by update_control_labels
+            ui::spawn_menu_item(builder, font, index, "", row);
+        }
+    });
+}
+
+fn close_settings_menu(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    hidden: Query<Entity, With<HiddenBySettings>>,
+) {
+    for menu in &hidden {
+        commands
+            .entity(menu)
+            .insert(Visibility::Inherited)
+            .remove::<HiddenBySettings>();
+    }
+
+    if let Err(e) = settings.save() {
+        log::error!("could not save settings: {e}");
+    }
+}
+
+/// The next option after `current`, wrapping around.
+fn cycle<T: PartialEq + Clone>(options: &[T], current: &T, forward: bool) -> T {
+    let index = options.iter().position(|o| o == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % options.len()
+    } else {
+        (index + options.len() - 1) % options.len()
+    };
+    options[next].clone()
+}
+
+fn settings_action(
+    actions: Res<ButtonInput<Action>>,
+    mut reader: EventReader<MenuActivated>,
+    lists: Query<(&MenuList, &Children)>,
+    rows: Query<&SettingsRow>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<NextState<SettingsMenu>>,
+    mut page: ResMut<NextState<SettingsPage>>,
+) {
+    if actions.just_pressed(Action::Cancel) {
+        state.set(SettingsMenu::Closed);
+        return;
+    }
+
+    // confirming steps forward, left and right step through the options of the selected row
+    let mut changes = reader
+        .read()
+        .filter_map(|MenuActivated(entity)| Some((*rows.get(*entity).ok()?, true)))
+        .collect::<Vec<_>>();
+    let left = actions.just_pressed(Action::Left);
+    let right = actions.just_pressed(Action::Right);
+    if left || right {
+        for (list, children) in &lists {
+            let selected = children
+                .iter()
+                .filter_map(|&child| rows.get(child).ok())
+                .nth(list.selected);
+            if let Some(&row) = selected {
+                changes.push((row, right));
+            }
+        }
+    }
+
+    for (row, forward) in changes {
+        let step = |volume: f32| {
+            let volume = (volume * 10.0).round() as i32 + if forward { 1 } else { -1 };
+            volume.rem_euclid(11) as f32 / 10.0
+        };
+
+        match row {
+            SettingsRow::MusicVolume => settings.music_volume = step(settings.music_volume),
+            SettingsRow::SfxVolume => settings.sfx_volume = step(settings.sfx_volume),
+            SettingsRow::Window => {
+                let modes = [
+                    WindowSetting::Windowed,
+                    WindowSetting::Borderless,
+                    WindowSetting::Fullscreen,
+                ];
+                settings.window = cycle(&modes, &settings.window, forward);
+            }
+            SettingsRow::Resolution => {
+                settings.resolution = cycle(&RESOLUTIONS, &settings.resolution, forward);
+            }
+            SettingsRow::TextSpeed => {
+                let speeds = TEXT_SPEEDS.map(|(speed, _)| speed);
+                settings.text_speed = cycle(&speeds, &settings.text_speed, forward);
+            }
+            SettingsRow::Language => {
+                let languages = locale::languages();
+                if !languages.is_empty() {
+                    settings.language = cycle(&languages, &settings.language, forward);
+                }
+            }
+            SettingsRow::Diagnostics => settings.show_diagnostics = !settings.show_diagnostics,
+            SettingsRow::Controls => page.set(SettingsPage::Controls),
+            SettingsRow::Back => state.set(SettingsMenu::Closed),
+        }
+    }
+}
+
+fn update_labels(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    rows: Query<(&SettingsRow, &Children), Added<SettingsRow>>,
+    all_rows: Query<(&SettingsRow, &Children)>,
+    mut labels: Query<&mut Text>,
+) {
+    let rows = if settings.is_changed() || locale.is_changed() {
+        all_rows.iter().collect::<Vec<_>>()
+    } else {
+        rows.iter().collect()
+    };
+
+    for (row, children) in rows {
+        let label = match row {
+            SettingsRow::MusicVolume => locale.format(
+                "settings-music",
+                &[("percent", &(settings.music_volume * 100.0).round())],
+            ),
+            SettingsRow::SfxVolume => locale.format(
+                "settings-sounds",
+                &[("percent", &(settings.sfx_volume * 100.0).round())],
+            ),
+            SettingsRow::Window => {
+                let mode = match settings.window {
+                    WindowSetting::Windowed => "window-windowed",
+                    WindowSetting::Borderless => "window-borderless",
+                    WindowSetting::Fullscreen => "window-fullscreen",
+                };
+                locale.format("settings-window", &[("mode", &locale.text(mode))])
+            }
+            SettingsRow::Resolution => locale.format(
+                "settings-resolution",
+                &[
+                    ("width", &settings.resolution.0),
+                    ("height", &settings.resolution.1),
+                ],
+            ),
+            SettingsRow::TextSpeed => {
+                let name = TEXT_SPEEDS
+                    .iter()
+                    .find(|(speed, _)| *speed == settings.text_speed)
+                    .map_or("text-speed-custom", |(_, name)| name);
+                locale.format("settings-text-speed", &[("speed", &locale.text(name))])
+            }
+            SettingsRow::Language => locale.format(
+                "settings-language",
+                &[("language", &locale.text("language-name"))],
+            ),
+            SettingsRow::Diagnostics => {
+                let state = if settings.show_diagnostics {
+                    "settings-on"
+                } else {
+                    "settings-off"
+                };
+                locale.format("settings-diagnostics", &[("state", &locale.text(state))])
+            }
+            SettingsRow::Controls => locale.text("controls-title").to_string(),
+            SettingsRow::Back => locale.text("menu-back").to_string(),
+        };
+
+        let mut texts = labels.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.0.clone_from(&label);
+        }
+    }
+}
+
+fn controls_action(
+    actions: Res<ButtonInput<Action>>,
+    mut reader: EventReader<MenuActivated>,
+    rows: Query<&ControlsRow>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut page: ResMut<NextState<SettingsPage>>,
+) {
+    if actions.just_pressed(Action::Cancel) {
+        page.set(SettingsPage::Main);
+        return;
+    }
+
+    for MenuActivated(entity) in reader.read() {
+        match rows.get(*entity) {
+            Ok(ControlsRow::Rebind(action)) => rebinding.0 = Some(*action),
+            Ok(ControlsRow::Reset) => settings.bindings = Bindings::default(),
+            Ok(ControlsRow::Back) => page.set(SettingsPage::Main),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Binds the next key or gamepad button pressed to the action being rebound.
+///
+/// A key replaces the keyboard bindings of the action and a button its gamepad
+/// bindings, escape gives up without changing anything.
+fn capture_binding(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    // the menus shouldn't react to the press that's being bound
+    for action in Action::ALL {
+        actions.clear_just_pressed(action);
+    }
+
+    if let Some(&key) = keyboard_input.get_just_pressed().next() {
+        if key != KeyCode::Escape {
+            settings.bindings.rebind_key(action, key);
+        }
+        rebinding.0 = None;
+    } else if let Some(&button) = gamepads
+        .iter()
+        .find_map(|gamepad| gamepad.get_just_pressed().next())
+    {
+        settings.bindings.rebind_button(action, button);
+        rebinding.0 = None;
+    }
+}
+
+fn stop_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+fn update_control_labels(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    rebinding: Res<Rebinding>,
+    rows: Query<(&ControlsRow, &Children), Added<ControlsRow>>,
+    all_rows: Query<(&ControlsRow, &Children)>,
+    mut labels: Query<&mut Text>,
+) {
+    let rows = if settings.is_changed() || rebinding.is_changed() {
+        all_rows.iter().collect::<Vec<_>>()
+    } else {
+        rows.iter().collect()
+    };
+
+    for (row, children) in rows {
+        let label = match row {
+            ControlsRow::Rebind(action) if rebinding.0 == Some(*action) => {
+                locale.format("controls-waiting", &[("action", &format!("{action:?}"))])
+            }
+            ControlsRow::Rebind(action) => {
+                let keys = settings
+                    .bindings
+                    .keys(*action)
+                    .iter()
+                    .map(|&key| input::key_name(key))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let buttons = settings
+                    .bindings
+                    .buttons(*action)
+                    .iter()
+                    .map(|button| format!("{button:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{action:?}: {keys} / {buttons}")
+            }
+            ControlsRow::Reset => locale.text("controls-reset").to_string(),
+            ControlsRow::Back => locale.text("menu-back").to_string(),
+        };
+
+        let mut texts = labels.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.0.clone_from(&label);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+use crate::input::Action;
+
+const BUTTON_COLOR: Color = Color::srgb(0.12, 0.12, 0.18);
+const SELECTED_COLOR: Color = Color::srgb(0.25, 0.55, 0.3);
+const DISABLED_TEXT: Color = Color::srgb(0.45, 0.45, 0.45);
+
+/// A vertical list of buttons, driven by the keyboard, a gamepad or the mouse.
+///
+/// Only lists that are visible react to buttons, so a menu can be hidden
+/// while another one is shown on top of it.
+#[derive(Component, Default)]
+pub struct MenuList {
+    pub selected: usize,
+}
+
+/// A button in a [`MenuList`], numbered from the top.
+#[derive(Component)]
+pub struct MenuItem(pub usize);
+
+/// Greys out a [`MenuItem`] and skips it when navigating.
+#[derive(Component)]
+pub struct Disabled;
+
+/// Sent with the button's entity when a [`MenuItem`] is clicked or confirmed.
+#[derive(Event, Debug)]
+pub struct MenuActivated(pub Entity);
+
+pub fn ui_plugin(app: &mut App) {
+    app.add_event::<MenuActivated>()
+        .add_systems(Update, (navigate_menus, highlight_menus).chain());
+}
+
+/// The node for a centered column of buttons.
+pub fn menu_column() -> Node {
+    Node {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Stretch,
+        row_gap: Val::Px(12.),
+        ..default()
+    }
+}
+
+/// Adds a button labelled `label` to a [`MenuList`], with `action` to tell it apart.
+pub fn spawn_menu_item(
+    builder: &mut ChildBuilder,
+    font: &Handle<Font>,
+    index: usize,
+    label: impl Into<String>,
+    action: impl Bundle,
+) {
+    builder
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(30.), Val::Px(10.)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+            MenuItem(index),
+            action,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new(label),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 36.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn navigate_menus(
+    actions: Res<ButtonInput<Action>>,
+    mut lists: Query<(&mut MenuList, &Children, &InheritedVisibility)>,
+    items: Query<(Entity, &MenuItem, &Interaction, Has<Disabled>)>,
+    changed: Query<(), Changed<Interaction>>,
+    mut writer: EventWriter<MenuActivated>,
+) {
+    let up = actions.just_pressed(Action::Up);
+    let down = actions.just_pressed(Action::Down);
+    let confirm = actions.just_pressed(Action::Confirm);
+
+    for (mut list, children, visibility) in &mut lists {
+        if !visibility.get() {
+            continue;
+        }
+
+        let mut buttons = items.iter_many(children).collect::<Vec<_>>();
+        buttons.sort_by_key(|(_, item, ..)| item.0);
+        let enabled = buttons
+            .iter()
+            .filter(|(.., disabled)| !disabled)
+            .map(|(_, item, ..)| item.0)
+            .collect::<Vec<_>>();
+        if enabled.is_empty() {
+            continue;
+        }
+
+        // the mouse takes over whenever it moves onto a button
+        for &(entity, item, interaction, disabled) in &buttons {
+            if disabled || !changed.contains(entity) {
+                continue;
+            }
+            match interaction {
+                Interaction::Hovered => list.selected = item.0,
+                Interaction::Pressed => {
+                    list.selected = item.0;
+                    writer.send(MenuActivated(entity));
+                }
+                Interaction::None => {}
+            }
+        }
+
+        let position = enabled.iter().position(|&i| i == list.selected);
+        list.selected = match position {
+            Some(i) if up => enabled[(i + enabled.len() - 1) % enabled.len()],
+            Some(i) if down => enabled[(i + 1) % enabled.len()],
+            Some(_) => list.selected,
+            None => enabled[0],
+        };
+
+        if confirm {
+            if let Some(&(entity, ..)) =
+                buttons.iter().find(|(_, item, ..)| item.0 == list.selected)
+            {
+                writer.send(MenuActivated(entity));
+            }
+        }
+    }
+}
+
+fn highlight_menus(
+    lists: Query<(&MenuList, &Children)>,
+    mut items: Query<(&MenuItem, &mut BackgroundColor, &Children, Has<Disabled>)>,
+    mut labels: Query<&mut TextColor>,
+) {
+    for (list, children) in &lists {
+        let mut iter = items.iter_many_mut(children);
+        while let Some((item, mut background, label, disabled)) = iter.fetch_next() {
+            *background = if item.0 == list.selected && !disabled {
+                SELECTED_COLOR.into()
+            } else {
+                BUTTON_COLOR.into()
+            };
+
+            let color = if disabled {
+                DISABLED_TEXT
+            } else {
+                Color::WHITE
+            };
+            let mut label = labels.iter_many_mut(label);
+            while let Some(mut text_color) = label.fetch_next() {
+                text_color.0 = color;
+            }
+        }
+    }
+}